@@ -1,4 +1,6 @@
+mod json;
 mod logger;
 mod parser;
 
+pub use json::{write_book_event, write_error, write_report, write_summary_events};
 pub use logger::CliLogger;
@@ -0,0 +1,226 @@
+use crate::{BookReport, BookStatus, EntryReport, Error, Removed, Report, ReportFormat};
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// Writes a [`Report`] in the JSON or NDJSON format.
+///
+/// NDJSON has a `book` event per line, a `summary` event and an `error` event
+/// if [`Report::error`] is set. JSON is a single object with `books`, `summary`
+/// and `error`. Keys do not depend on the language. Writes nothing for
+/// [`ReportFormat::Text`].
+pub fn write_report(w: &mut impl Write, report: &Report, format: ReportFormat) -> io::Result<()> {
+    match format {
+        ReportFormat::Text => Ok(()),
+        ReportFormat::Ndjson => {
+            for b in &report.books {
+                write_book_event(w, b)?;
+            }
+            write_summary_events(w, report)
+        }
+        ReportFormat::Json => {
+            let books: Vec<_> = report
+                .books
+                .iter()
+                .map(|b| {
+                    let mut obj = Object::new();
+                    book(&mut obj, b);
+                    obj.end()
+                })
+                .collect();
+            let mut sum = Object::new();
+            summary(&mut sum, report);
+
+            let mut obj = Object::new();
+            obj.raw("books", &format!("[{}]", books.join(",")));
+            obj.raw("summary", &sum.end());
+            match &report.error {
+                Some(e) => obj.raw("error", &error(e)),
+                None => obj.raw("error", "null"),
+            }
+            writeln!(w, "{}", obj.end())
+        }
+    }
+}
+
+/// Writes an NDJSON `book` event, as soon as the book is processed.
+pub fn write_book_event(w: &mut impl Write, b: &BookReport) -> io::Result<()> {
+    let mut obj = Object::new();
+    obj.str("event", "book");
+    book(&mut obj, b);
+    writeln!(w, "{}", obj.end())
+}
+
+/// Writes NDJSON `summary` and `error` events of a [`Report`], following its
+/// `book` events.
+pub fn write_summary_events(w: &mut impl Write, report: &Report) -> io::Result<()> {
+    let mut obj = Object::new();
+    obj.str("event", "summary");
+    summary(&mut obj, report);
+    writeln!(w, "{}", obj.end())?;
+    match &report.error {
+        Some(e) => write_error(w, e, ReportFormat::Ndjson),
+        None => Ok(()),
+    }
+}
+
+/// Writes an error that stopped the run before any book, as an `error` event.
+///
+/// Writes nothing for [`ReportFormat::Text`].
+pub fn write_error(w: &mut impl Write, err: &Error, format: ReportFormat) -> io::Result<()> {
+    if format == ReportFormat::Text {
+        return Ok(());
+    }
+    let mut obj = Object::new();
+    if format == ReportFormat::Ndjson {
+        obj.str("event", "error");
+    }
+    obj.raw("error", &error(err));
+    writeln!(w, "{}", obj.end())
+}
+
+fn book(obj: &mut Object, b: &BookReport) {
+    obj.path("src", &b.src);
+    obj.path("dest", &b.dest);
+    obj.str("status", b.status.as_str());
+    obj.num("input_size", b.input_size);
+    match b.output_size {
+        Some(n) => obj.num("output_size", n),
+        None => obj.raw("output_size", "null"),
+    }
+    obj.raw("removed", &removed(&b.removed));
+    obj.num("removed_bytes", b.removed_bytes);
+    obj.num("failed_entries", b.failed_entries);
+    let entries: Vec<_> = b.entries.iter().map(entry).collect();
+    obj.raw("entries", &format!("[{}]", entries.join(",")));
+    obj.num("duration_ms", millis(b.duration));
+    match &b.error {
+        Some(e) => obj.raw("error", &error(e)),
+        None => obj.raw("error", "null"),
+    }
+}
+
+fn entry(e: &EntryReport) -> String {
+    let mut obj = Object::new();
+    obj.str("name", &e.name);
+    obj.str("status", e.status.as_str());
+    obj.raw("removed", &removed(&e.removed));
+    obj.num("removed_bytes", e.removed_bytes);
+    match &e.error {
+        Some(e) => obj.raw("error", &error(e)),
+        None => obj.raw("error", "null"),
+    }
+    obj.end()
+}
+
+fn summary(obj: &mut Object, report: &Report) {
+    let books = &report.books;
+    obj.num("books", books.len() as u64);
+    for status in [
+        BookStatus::Cleaned,
+        BookStatus::Skipped,
+        BookStatus::Failed,
+        BookStatus::Overwritten,
+        BookStatus::DryRun,
+        BookStatus::Partial,
+    ] {
+        obj.num(status.as_str(), report.count(status) as u64);
+    }
+    obj.num("input_size", books.iter().map(|b| b.input_size).sum());
+    obj.num(
+        "output_size",
+        books.iter().filter_map(|b| b.output_size).sum(),
+    );
+    obj.raw("removed", &removed(&report.removed()));
+    obj.num("removed_bytes", report.removed_bytes());
+    obj.num(
+        "failed_entries",
+        books.iter().map(|b| b.failed_entries).sum(),
+    );
+    obj.num(
+        "duration_ms",
+        millis(books.iter().map(|b| b.duration).sum()),
+    );
+}
+
+fn removed(removed: &Removed) -> String {
+    let mut obj = Object::new();
+    for (name, n) in &removed.tags {
+        obj.num(name, *n as u64);
+    }
+    obj.end()
+}
+
+fn error(err: &Error) -> String {
+    let mut obj = Object::new();
+    obj.str("kind", err.kind());
+    match err.path() {
+        Some(p) => obj.path("path", p),
+        None => obj.raw("path", "null"),
+    }
+    obj.str("message", &err.to_string());
+    obj.end()
+}
+
+fn millis(d: Duration) -> u64 {
+    u64::try_from(d.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// A JSON object, written in order of added keys.
+struct Object(String);
+
+impl Object {
+    fn new() -> Object {
+        Object(String::from("{"))
+    }
+
+    fn raw(&mut self, key: &str, value: &str) {
+        if self.0.len() > 1 {
+            self.0.push(',');
+        }
+        escape(&mut self.0, key);
+        self.0.push(':');
+        self.0.push_str(value);
+    }
+
+    fn str(&mut self, key: &str, value: &str) {
+        let mut s = String::new();
+        escape(&mut s, value);
+        self.raw(key, &s);
+    }
+
+    fn num(&mut self, key: &str, value: u64) {
+        self.raw(key, &value.to_string());
+    }
+
+    fn path(&mut self, key: &str, value: &Path) {
+        self.str(key, &value.to_string_lossy());
+    }
+
+    fn end(mut self) -> String {
+        self.0.push('}');
+        self.0
+    }
+}
+
+/// Pushes a quoted JSON string.
+fn escape(dest: &mut String, s: &str) {
+    dest.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => dest.push_str("\\\""),
+            '\\' => dest.push_str("\\\\"),
+            '\n' => dest.push_str("\\n"),
+            '\r' => dest.push_str("\\r"),
+            '\t' => dest.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(dest, "\\u{:04x}", c as u32);
+            }
+            c => dest.push(c),
+        }
+    }
+    dest.push('"');
+}
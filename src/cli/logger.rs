@@ -5,8 +5,13 @@ use std::{
 };
 use supports_color::{Stream, on};
 
-static CLI_LOGGER: CliLogger = CliLogger;
-pub struct CliLogger;
+static CLI_LOGGER: CliLogger = CliLogger { is_stderr: false };
+static CLI_STDERR_LOGGER: CliLogger = CliLogger { is_stderr: true };
+
+pub struct CliLogger {
+    /// Write all messages to stderr.
+    is_stderr: bool,
+}
 
 static STDERR_ON_COLOR: LazyLock<bool> = LazyLock::new(|| on(Stream::Stderr).is_some());
 static STDOUT_ON_COLOR: LazyLock<bool> = LazyLock::new(|| on(Stream::Stdout).is_some());
@@ -17,6 +22,13 @@ impl CliLogger {
         log::set_max_level(LevelFilter::Info);
     }
 
+    /// Inits the logger writing all messages to stderr, keeping stdout for
+    /// machine-readable output.
+    pub fn init_stderr() {
+        log::set_logger(&CLI_STDERR_LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Info);
+    }
+
     fn prf_prefix(level: Level) -> &'static str {
         match level {
             Level::Error if *STDERR_ON_COLOR => "\x1b[31m",
@@ -64,6 +76,9 @@ impl Log for CliLogger {
         let msg = msg.as_bytes();
 
         match level {
+            _ if self.is_stderr => {
+                let _ = io::stderr().write_all(msg);
+            }
             Level::Error | Level::Warn => {
                 let _ = io::stderr()
                     .write_all(msg)
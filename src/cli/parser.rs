@@ -1,11 +1,22 @@
-use crate::{Config, Input, Lang, Msg, Output, Tags, msg};
+#[cfg(feature = "images")]
+use crate::Images;
+use crate::{
+    Config, Input, InputFileType, InputSource, Lang, Msg, Output, OutputStyle, Pis, ReportFormat,
+    Selector, StripAttr, Tags, Validation, config::input::is_glob, msg,
+};
 use clap::{
     Arg, ArgAction, ArgMatches, Command, CommandFactory, Error, FromArgMatches, Parser,
     builder::{TypedValueParser, ValueParser},
     error::{ContextKind, ContextValue, ErrorKind},
     value_parser,
 };
-use std::{ffi::OsStr, str::FromStr};
+use indexmap::IndexSet;
+use std::{
+    ffi::OsStr,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 type Result<T> = std::result::Result<T, clap::Error>;
 
@@ -27,32 +38,111 @@ impl FromArgMatches for Config {
 
         if m.get_flag("help") {
             let mut cmd = Config::command();
-            if let Err(_) = cmd.print_help() {
+            if cmd.print_help().is_err() {
                 println!("{}", cmd.render_help());
             }
             std::process::exit(0);
         }
 
-        let input = match m.remove_one::<Input>("input") {
-            Some(i) => i,
-            None => Input::new(".").unwrap(),
+        let sniff = m.get_flag("sniff");
+        let inputs: Vec<PathBuf> = m
+            .remove_many::<PathBuf>("input")
+            .map(|xs| xs.collect())
+            .unwrap_or_default();
+        let files_from = m.remove_one::<PathBuf>("files-from");
+        let input = match (&inputs[..], &files_from) {
+            ([], None) => Input::new(".").unwrap(),
+            ([p], None) if p.exists() || !is_glob(p) => {
+                parse_path("input", p, |p| Input::new_with(p, sniff))?
+            }
+            _ => {
+                let mut xs = Vec::new();
+                for p in &inputs {
+                    xs.extend(parse_path("input", p, |p| InputSource::new(p, sniff))?);
+                }
+                if let Some(p) = &files_from {
+                    xs.extend(parse_path("files-from", p, |p| {
+                        InputSource::read_list(p, sniff)
+                    })?);
+                }
+                Input::many(xs)
+            }
         };
-        let output = match m.remove_one::<Output>("output") {
-            Some(o) => o,
-            None => Output::try_from_input(&input).unwrap(),
+        let output = m.remove_one::<PathBuf>("output");
+        let stdout = output.as_deref() == Some(Path::new(STDOUT_PATH));
+        let output = match output {
+            Some(p) if !stdout => parse_path("output", &p, Output::new)?,
+            _ => Output::try_from_input(&input).unwrap(),
         };
 
+        let format = m.remove_one::<ReportFormat>("format").unwrap_or_default();
+        if stdout && format != ReportFormat::Text {
+            let mut cmd = Config::command();
+            cmd.build();
+            let msg = format!("{}\n", msg!(StdoutIsTakenByBook));
+            return Err(Error::raw(ErrorKind::ArgumentConflict, msg).with_cmd(&cmd));
+        }
+
+        #[cfg(feature = "images")]
+        let images = Images {
+            max_width: m.remove_one::<u32>("max-width"),
+            max_height: m.remove_one::<u32>("max-height"),
+            grayscale: m.get_flag("grayscale"),
+            quality: m.remove_one::<u8>("jpeg-quality"),
+        };
+
+        let mut tags = Tags::default();
+        if let Some(list) = m.remove_one::<IndexSet<Selector>>("tags") {
+            tags.list = list;
+        } else {
+            // The default list removes images, which are to be recompressed.
+            #[cfg(feature = "images")]
+            if images.is_enabled() {
+                tags.list.clear();
+            }
+        }
+        if let Some(list) = m.remove_one::<IndexSet<Selector>>("unwrap-tags") {
+            tags.unwrap = list;
+        }
+        if let Some(list) = m.remove_one::<IndexSet<StripAttr>>("strip-attrs") {
+            tags.strip_attrs = list;
+        }
+        if let Some(list) = m.remove_one::<IndexSet<Selector>>("prune-empty") {
+            tags.prune_empty = list;
+        }
+        tags.strip_comments = m.get_flag("strip-comments");
+        tags.strip_doctype = m.get_flag("strip-doctype");
+        if m.get_flag("strip-pis") {
+            tags.pis = Pis::Strip;
+        } else if let Some(pis) = m.remove_one::<Pis>("keep-pis") {
+            tags.pis = pis;
+        }
+        tags.keep = m.get_flag("keep");
+        tags.local_names = m.get_flag("local-names");
+        tags.fix_refs = m.get_flag("fix-refs");
+        tags.keep_cover = m.get_flag("keep-cover");
+        tags.max_binary_size = m.remove_one::<u64>("max-binary-size");
+
         Ok(Config {
             input,
             output,
+            stdout,
+            style: m.remove_one::<OutputStyle>("style").unwrap_or_default(),
             recursive: *m.get_one::<u8>("recursive").unwrap_or(&0),
-            tags: m
-                .remove_one::<Tags>("tags")
-                .unwrap_or_else(|| Tags::default()),
+            sniff,
+            tags,
+            #[cfg(feature = "images")]
+            images,
             zip: m.get_flag("zip"),
             unzip: m.get_flag("unzip"),
+            container: m.remove_one::<InputFileType>("container"),
+            utf8: m.get_flag("utf8"),
+            lenient: m.get_flag("lenient"),
+            validation: m.remove_one::<Validation>("validate").unwrap_or_default(),
+            dry_run: m.get_flag("dry-run"),
             force: m.get_flag("force"),
             exit_on_err: m.get_flag("exit-on-err"),
+            format,
             jobs: *m.get_one::<u8>("jobs").unwrap_or(&1),
         })
     }
@@ -65,7 +155,7 @@ impl FromArgMatches for Config {
 
 impl CommandFactory for Config {
     fn command() -> Command {
-        Command::new(env!("CARGO_PKG_NAME"))
+        let cmd = Command::new(env!("CARGO_PKG_NAME"))
             .version(concat!("v", env!("CARGO_PKG_VERSION")))
             .disable_help_flag(true)
             .disable_version_flag(true)
@@ -76,7 +166,15 @@ impl CommandFactory for Config {
                     .long("input")
                     .value_name("path")
                     .help(msg!(HelpInput))
-                    .value_parser(ValueParser::new(InputParser)),
+                    .action(ArgAction::Append)
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(
+                Arg::new("files-from")
+                    .long("files-from")
+                    .value_name("file")
+                    .help(msg!(HelpFilesFrom))
+                    .value_parser(value_parser!(PathBuf)),
             )
             .arg(
                 Arg::new("output")
@@ -84,7 +182,7 @@ impl CommandFactory for Config {
                     .long("output")
                     .value_name("dir")
                     .help(msg!(HelpOutput))
-                    .value_parser(ValueParser::new(OutputParser)),
+                    .value_parser(value_parser!(PathBuf)),
             )
             .arg(
                 Arg::new("recursive")
@@ -96,13 +194,111 @@ impl CommandFactory for Config {
                     .default_missing_value("16")
                     .value_parser(value_parser!(u8).range(1..)),
             )
+            .arg(
+                Arg::new("sniff")
+                    .long("sniff")
+                    .help(msg!(HelpSniff))
+                    .action(ArgAction::SetTrue),
+            )
             .arg(
                 Arg::new("tags")
                     .short('t')
                     .long("tags")
                     .value_name("n[,m...]")
                     .help(msg!(HelpTags))
-                    .value_parser(ValueParser::new(TagsParser)),
+                    .value_parser(ValueParser::new(TagListParser)),
+            )
+            .arg(
+                Arg::new("unwrap-tags")
+                    .short('u')
+                    .long("unwrap-tags")
+                    .alias("unwrap")
+                    .value_name("n[,m...]")
+                    .help(msg!(HelpUnwrapTags))
+                    .value_parser(ValueParser::new(TagListParser)),
+            )
+            .arg(
+                Arg::new("strip-attrs")
+                    .long("strip-attrs")
+                    .value_name("[n@]a[,...]")
+                    .help(msg!(HelpStripAttrs))
+                    .value_parser(ValueParser::new(StripAttrsParser)),
+            )
+            .arg(
+                Arg::new("prune-empty")
+                    .long("prune-empty")
+                    .value_name("n[,m...]")
+                    .help(msg!(HelpPruneEmpty))
+                    .num_args(0..=1)
+                    .default_missing_value("p,section")
+                    .value_parser(ValueParser::new(TagListParser)),
+            )
+            .arg(
+                Arg::new("strip-comments")
+                    .long("strip-comments")
+                    .help(msg!(HelpStripComments))
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("strip-pis")
+                    .long("strip-pis")
+                    .help(msg!(HelpStripPis))
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("keep-pis")
+                    .long("keep-pis")
+                    .value_name("regex")
+                    .help(msg!(HelpKeepPis))
+                    .conflicts_with("strip-pis")
+                    .value_parser(ValueParser::new(PisParser)),
+            )
+            .arg(
+                Arg::new("strip-doctype")
+                    .long("strip-doctype")
+                    .help(msg!(HelpStripDoctype))
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("keep")
+                    .short('k')
+                    .long("keep")
+                    .alias("keep-only")
+                    .help(msg!(HelpKeep))
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("local-names")
+                    .long("local-names")
+                    .help(msg!(HelpLocalNames))
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("fix-refs")
+                    .long("fix-refs")
+                    .help(msg!(HelpFixRefs))
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("keep-cover")
+                    .long("keep-cover")
+                    .help(msg!(HelpKeepCover))
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("max-binary-size")
+                    .long("max-binary-size")
+                    .value_name("size")
+                    .help(msg!(HelpMaxBinarySize))
+                    .value_parser(ValueParser::new(SizeParser)),
+            )
+            .arg(
+                Arg::new("style")
+                    .short('s')
+                    .long("style")
+                    .value_name("preserve|minify|pretty")
+                    .help(msg!(HelpStyle))
+                    .value_parser(ValueParser::new(OutputStyle::from_str)),
             )
             .arg(
                 Arg::new("zip")
@@ -120,6 +316,43 @@ impl CommandFactory for Config {
                     .conflicts_with("zip")
                     .action(ArgAction::SetTrue),
             )
+            .arg(
+                Arg::new("container")
+                    .long("container")
+                    .value_name("ext")
+                    .help(msg!(HelpContainer))
+                    .conflicts_with_all(["zip", "unzip"])
+                    .value_parser(ValueParser::new(InputFileType::from_str)),
+            )
+            .arg(
+                Arg::new("utf8")
+                    .long("utf8")
+                    .alias("to-utf8")
+                    .help(msg!(HelpUtf8))
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("lenient")
+                    .long("lenient")
+                    .alias("repair")
+                    .help(msg!(HelpLenient))
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("validate")
+                    .long("validate")
+                    .value_name("warn|fail")
+                    .help(msg!(HelpValidate))
+                    .num_args(0..=1)
+                    .default_missing_value("warn")
+                    .value_parser(ValueParser::new(Validation::from_str)),
+            )
+            .arg(
+                Arg::new("dry-run")
+                    .long("dry-run")
+                    .help(msg!(HelpDryRun))
+                    .action(ArgAction::SetTrue),
+            )
             .arg(
                 Arg::new("force")
                     .short('f')
@@ -143,8 +376,41 @@ impl CommandFactory for Config {
                     .value_name("n")
                     .help(msg!(HelpJobs))
                     .value_parser(value_parser!(u8).range(1..)),
+            );
+
+        #[cfg(feature = "images")]
+        let cmd = cmd
+            .next_help_heading("Images")
+            .arg(
+                Arg::new("max-width")
+                    .long("max-width")
+                    .value_name("px")
+                    .help(msg!(HelpMaxWidth))
+                    .value_parser(value_parser!(u32).range(1..)),
+            )
+            .arg(
+                Arg::new("max-height")
+                    .long("max-height")
+                    .value_name("px")
+                    .help(msg!(HelpMaxHeight))
+                    .value_parser(value_parser!(u32).range(1..)),
+            )
+            .arg(
+                Arg::new("grayscale")
+                    .long("grayscale")
+                    .alias("greyscale")
+                    .help(msg!(HelpGrayscale))
+                    .action(ArgAction::SetTrue),
             )
-            .next_help_heading("Other")
+            .arg(
+                Arg::new("jpeg-quality")
+                    .long("jpeg-quality")
+                    .value_name("n")
+                    .help(msg!(HelpJpegQuality))
+                    .value_parser(value_parser!(u8).range(1..=100)),
+            );
+
+        cmd.next_help_heading("Other")
             .arg(
                 Arg::new("lang")
                     .short('l')
@@ -154,6 +420,13 @@ impl CommandFactory for Config {
                     .help(msg!(HelpLang))
                     .value_parser(ValueParser::new(Lang::from_str)),
             )
+            .arg(
+                Arg::new("format")
+                    .long("format")
+                    .value_name("text|json|ndjson")
+                    .help(msg!(HelpFormat))
+                    .value_parser(ValueParser::new(ReportFormat::from_str)),
+            )
             .arg(
                 Arg::new("version")
                     .short('V')
@@ -189,38 +462,50 @@ macro_rules! ty_parser {
                 arg: Option<&Arg>,
                 value: &OsStr,
             ) -> Result<Self::Value> {
-                $try_from_os_str(value).map_err(|e| {
-                    let mut err = Error::new(ErrorKind::InvalidValue).with_cmd(&cmd);
-
-                    if let Some(arg) = arg {
-                        err.insert(
-                            ContextKind::InvalidArg,
-                            ContextValue::String(arg.to_string()),
-                        );
-                    }
-                    err.insert(
-                        ContextKind::InvalidValue,
-                        ContextValue::String(format!(
-                            "'{}' (reason: {})",
-                            value.to_string_lossy(),
-                            e
-                        )),
-                    );
-
-                    err
-                })
+                $try_from_os_str(value).map_err(|e| invalid_value(cmd, arg, value, e))
             }
         }
     };
 }
 
-ty_parser!(InputParser, Input, Input::new);
-ty_parser!(OutputParser, Output, Output::new);
-ty_parser!(TagsParser, Tags, Tags::fallible_new);
+fn invalid_value(cmd: &Command, arg: Option<&Arg>, value: &OsStr, e: impl fmt::Display) -> Error {
+    let mut err = Error::new(ErrorKind::InvalidValue).with_cmd(cmd);
 
-impl Tags {
-    /// Always returns `Ok`.
-    fn fallible_new(os: impl AsRef<OsStr>) -> std::result::Result<Tags, String> {
-        Ok(Self::new(os))
+    if let Some(arg) = arg {
+        err.insert(
+            ContextKind::InvalidArg,
+            ContextValue::String(arg.to_string()),
+        );
     }
+    err.insert(
+        ContextKind::InvalidValue,
+        ContextValue::String(format!("'{}' (reason: {})", value.to_string_lossy(), e)),
+    );
+
+    err
 }
+
+/// Path of `--output` writing the book to stdout.
+const STDOUT_PATH: &str = "-";
+
+fn parse_path<'a, T>(
+    id: &str,
+    path: &'a Path,
+    new: impl FnOnce(&'a Path) -> crate::Result<T>,
+) -> Result<T> {
+    new(path).map_err(|e| {
+        let mut cmd = Config::command();
+        cmd.build();
+        let arg = cmd.get_arguments().find(|a| a.get_id() == id);
+        invalid_value(&cmd, arg, path.as_os_str(), e)
+    })
+}
+
+ty_parser!(PisParser, Pis, Pis::new_matching);
+ty_parser!(SizeParser, u64, Tags::parse_size);
+ty_parser!(TagListParser, IndexSet<Selector>, Tags::parse_list);
+ty_parser!(
+    StripAttrsParser,
+    IndexSet<StripAttr>,
+    Tags::parse_strip_attrs
+);
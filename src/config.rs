@@ -1,11 +1,19 @@
+#[cfg(feature = "images")]
+pub(crate) mod images;
 pub(crate) mod input;
 pub(crate) mod output;
+pub(crate) mod report;
 mod run;
 pub(crate) mod tags;
+pub(crate) mod validation;
 
-use input::Input;
-use output::Output;
+#[cfg(feature = "images")]
+use images::Images;
+use input::{Input, InputFileType};
+use output::{Output, OutputStyle};
+use report::ReportFormat;
 use tags::Tags;
+use validation::Validation;
 
 /// Clean configuration.
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -13,11 +21,31 @@ use tags::Tags;
 pub struct Config {
     pub input: Input,
     pub output: Output,
+    /// Write the book to stdout instead of [`Config::output`].
+    pub stdout: bool,
+    pub style: OutputStyle,
     pub recursive: u8,
+    /// Detect books by content instead of extension.
+    pub sniff: bool,
     pub tags: Tags,
+    #[cfg(feature = "images")]
+    pub images: Images,
     pub zip: bool,
     pub unzip: bool,
+    /// Save output books in the container, such as [`InputFileType::Fb2Gz`],
+    /// instead of `zip` or `unzip`.
+    pub container: Option<InputFileType>,
+    /// Re-encode output books to UTF-8.
+    pub utf8: bool,
+    /// Repair malformed XML instead of skipping books.
+    pub lenient: bool,
+    /// Validate output books against the FictionBook schema.
+    pub validation: Validation,
+    /// Report what would be removed, without writing or renaming anything.
+    pub dry_run: bool,
     pub force: bool,
     pub exit_on_err: bool,
+    /// Format of the CLI output.
+    pub format: ReportFormat,
     pub jobs: u8,
 }
@@ -0,0 +1,39 @@
+/// Embedded images recompression configuration.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Images {
+    /// Downscale images wider than the width, keeping the aspect ratio.
+    pub max_width: Option<u32>,
+    /// Downscale images higher than the height, keeping the aspect ratio.
+    pub max_height: Option<u32>,
+    /// Convert images to grayscale.
+    pub grayscale: bool,
+    /// JPEG quality (1-100) of recompressed images.
+    pub quality: Option<u8>,
+}
+
+impl Images {
+    /// JPEG quality used if [`Images::quality`] is not set.
+    pub const DEFAULT_QUALITY: u8 = 85;
+
+    /// Returns `true` if any option is set, so images are recompressed.
+    /// ```
+    /// use fb2_clean::Images;
+    ///
+    /// let mut images = Images::default();
+    /// assert!(!images.is_enabled());
+    /// images.grayscale = true;
+    /// assert!(images.is_enabled());
+    /// ```
+    pub fn is_enabled(&self) -> bool {
+        self.max_width.is_some()
+            || self.max_height.is_some()
+            || self.grayscale
+            || self.quality.is_some()
+    }
+
+    /// Returns [`Images::quality`] or [`Images::DEFAULT_QUALITY`].
+    pub fn quality(&self) -> u8 {
+        self.quality.unwrap_or(Self::DEFAULT_QUALITY)
+    }
+}
@@ -1,12 +1,38 @@
-use crate::Result;
+mod glob;
+mod sniff;
+
+pub(crate) use glob::is_glob;
+
+use crate::{Error, Msg, Result};
 use either::Either;
-use std::{fs, iter, path::Path};
+use log::{info, warn};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Cursor, Read, Seek},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+};
 
-/// Input directory OR file.
+/// Input directory OR file OR stdin, OR several of them.
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Input {
     Dir(Box<Path>),
     File(InputFile),
+    /// One book read from stdin, plain or packed, see [`Input::new`].
+    Stdin,
+    /// Several inputs, such as repeated paths, globs and lists of paths.
+    Many(Vec<InputSource>),
+}
+
+/// An input of [`Input::Many`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputSource {
+    /// Subdirectory of the output directory keeping a layout of the input.
+    pub subdir: Box<Path>,
+    pub input: Input,
 }
 
 /// Input file.
@@ -22,31 +48,121 @@ pub struct InputFile {
 pub enum InputFileType {
     Fb2,
     Fb2Zip,
+    /// A zip archive of one book, as [`InputFileType::Fb2Zip`].
+    Fbz,
+    Fb2Gz,
+    Fb2Bz2,
+    Fb2Xz,
+    /// A zip archive of many books, such as a library dump.
+    Zip,
 }
 
 impl Input {
     /// Returns iterator of files in the [`Input::Dir`] directory (non-recursive),
-    /// OR single-file iterator from [`Input::File`].
+    /// OR single-file iterator from [`Input::File`] or [`Input::Stdin`],
+    /// OR files of each [`Input::Many`] input in turn.
+    ///
+    /// For [`Input::Stdin`], reads stdin to detect a type of the book.
     pub fn iter(&self) -> impl Iterator<Item = InputFile> + use<> {
+        self.iter_with(false)
+    }
+
+    /// Returns iterator as [`Input::iter`], detecting books in the directory by
+    /// content, see [`InputFileType::sniff`]. Skips other files with a message.
+    pub fn iter_sniffed(&self) -> impl Iterator<Item = InputFile> + use<> {
+        self.iter_with(true)
+    }
+
+    pub(crate) fn iter_with(&self, sniff: bool) -> impl Iterator<Item = InputFile> + use<> {
         match self {
+            Self::Many(xs) => {
+                let files: Vec<InputFile> =
+                    xs.iter().flat_map(|x| x.input.iter_with(sniff)).collect();
+                Either::Right(Either::Right(files.into_iter()))
+            }
             Self::Dir(d) => Either::Left(
                 fs::read_dir(d)
                     .ok()
                     .into_iter()
                     .flat_map(|rd| rd.filter_map(std::result::Result::ok))
-                    .filter_map(|entry| {
+                    .filter(move |entry| !sniff || entry.file_type().is_ok_and(|t| t.is_file()))
+                    .filter_map(move |entry| {
                         let path = entry.path();
-                        get_input_file_type(&path).map(|ty| InputFile {
+                        let ty = if sniff {
+                            sniff_logged(&path)
+                        } else {
+                            get_input_file_type(&path)
+                                .filter(|ty| !ty.is_zip() || has_fb2_entry_logged(&path))
+                        };
+                        ty.map(|ty| InputFile {
                             ty,
                             path: path.into(),
                         })
                     }),
             ),
-            Self::File(f) => Either::Right(iter::once(f.clone())),
+            Self::File(f) => Either::Right(Either::Left(Some(f.clone()).into_iter())),
+            // Not a book OR an error is returned by the run, without a message.
+            Self::Stdin => {
+                Either::Right(Either::Left(InputFile::stdin().ok().flatten().into_iter()))
+            }
         }
     }
 }
 
+impl InputFile {
+    /// Path of [`Input::Stdin`].
+    pub const STDIN_PATH: &str = "-";
+
+    /// Returns a book of [`Input::Stdin`], detected by content, if it is a book.
+    pub(crate) fn stdin() -> Result<Option<InputFile>> {
+        let path = Path::new(Self::STDIN_PATH);
+        let ty = sniff::sniff_reader(Cursor::new(stdin_bytes()?))
+            .map_err(|e| Error::from(e).with_path(path))?;
+        Ok(ty.map(|ty| InputFile {
+            ty,
+            path: path.into(),
+        }))
+    }
+
+    pub fn is_stdin(&self) -> bool {
+        *self.path == *Path::new(Self::STDIN_PATH)
+    }
+
+    /// Returns a size of the book in bytes, or 0 if unknown.
+    pub(crate) fn len(&self) -> u64 {
+        if self.is_stdin() {
+            stdin_bytes().map_or(0, |b| b.len() as u64)
+        } else {
+            fs::metadata(&self.path).map_or(0, |m| m.len())
+        }
+    }
+
+    /// Opens the file, or stdin read once, as stages of cleaning read a book anew.
+    pub(crate) fn open(&self) -> Result<Box<dyn ReadSeek>> {
+        if self.is_stdin() {
+            Ok(Box::new(Cursor::new(stdin_bytes()?)))
+        } else {
+            Ok(Box::new(File::open(&self.path)?))
+        }
+    }
+}
+
+/// A readable and seekable source of a book.
+pub(crate) trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+static STDIN: OnceLock<Vec<u8>> = OnceLock::new();
+
+fn stdin_bytes() -> io::Result<&'static [u8]> {
+    if let Some(bytes) = STDIN.get() {
+        return Ok(bytes);
+    }
+    let mut bytes = Vec::new();
+    io::stdin().lock().read_to_end(&mut bytes)?;
+    Ok(STDIN.get_or_init(|| bytes))
+}
+
 impl Default for Input {
     fn default() -> Input {
         Input::new(".").unwrap_or_else(|_| Input::Dir(Path::new(".").into()))
@@ -54,48 +170,309 @@ impl Default for Input {
 }
 
 impl Input {
-    pub(crate) fn new(path: impl AsRef<Path>) -> Result<Input> {
-        let path = fs::canonicalize(path)?.into_boxed_path();
+    /// Creates an [`Input`] from a path of a directory or a book, or
+    /// [`Input::Stdin`] from `-`.
+    ///
+    /// # Errors
+    /// Returns an error if the path does not exist or is a file with an
+    /// unsupported extension.
+    pub fn new(path: impl AsRef<Path>) -> Result<Input> {
+        let path = path.as_ref();
+        if *path == *Path::new(InputFile::STDIN_PATH) {
+            return Ok(Self::Stdin);
+        }
+        let path = fs::canonicalize(path)
+            .map_err(|e| Error::from(e).with_path(path))?
+            .into_boxed_path();
 
         if path.is_dir() {
             Ok(Self::Dir(path))
         } else if let Some(ty) = get_input_file_type(&path) {
             Ok(Self::File(InputFile { ty, path }))
         } else {
-            Err("File has unsupported extension".into())
+            Err(Error::UnsupportedExtension(path))
         }
     }
+
+    /// Creates an [`Input`] as [`Input::new`], detecting a book by content,
+    /// see [`InputFileType::sniff`].
+    ///
+    /// # Errors
+    /// Returns an error if the path does not exist or is a file that is not a
+    /// book.
+    pub fn new_sniffed(path: impl AsRef<Path>) -> Result<Input> {
+        let path = path.as_ref();
+        if *path == *Path::new(InputFile::STDIN_PATH) {
+            return Ok(Self::Stdin);
+        }
+        let path = fs::canonicalize(path)
+            .map_err(|e| Error::from(e).with_path(path))?
+            .into_boxed_path();
+
+        if path.is_dir() {
+            return Ok(Self::Dir(path));
+        }
+        match InputFileType::sniff(&path) {
+            Ok(Some(ty)) => Ok(Self::File(InputFile { ty, path })),
+            Ok(None) => Err(Error::NotABook(path)),
+            Err(e) => Err(e.with_path(&path)),
+        }
+    }
+
+    /// Creates an [`Input`] as [`Input::new_sniffed`] if `sniff` is set, OR as
+    /// [`Input::new`].
+    pub(crate) fn new_with(path: impl AsRef<Path>, sniff: bool) -> Result<Input> {
+        if sniff {
+            Input::new_sniffed(path)
+        } else {
+            Input::new(path)
+        }
+    }
+
+    /// Creates an [`Input::Many`], suffixing subdirectories of different
+    /// directories of the same name, such as `books` and `books-2` for
+    /// `a/books` and `b/books`, so their books are not saved together.
+    /// ```
+    /// use fb2_clean::{Input, InputSource};
+    /// use std::path::Path;
+    ///
+    /// let src = |dir: &str| InputSource {
+    ///     subdir: Path::new("books").into(),
+    ///     input: Input::Dir(Path::new(dir).into()),
+    /// };
+    /// let Input::Many(xs) = Input::many(vec![src("a/books"), src("b/books")]) else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(Path::new("books-2"), &*xs[1].subdir);
+    /// ```
+    pub fn many(mut xs: Vec<InputSource>) -> Input {
+        let mut dirs: HashMap<Box<Path>, Box<Path>> = HashMap::new();
+        for x in xs.iter_mut() {
+            let Input::Dir(d) = &x.input else {
+                continue;
+            };
+            let mut subdir = x.subdir.clone();
+            for n in 2.. {
+                match dirs.get(&subdir) {
+                    Some(other) if other != d => {
+                        let mut s = x.subdir.as_os_str().to_owned();
+                        s.push(format!("-{}", n));
+                        subdir = PathBuf::from(s).into();
+                    }
+                    _ => break,
+                }
+            }
+            dirs.insert(subdir.clone(), d.clone());
+            x.subdir = subdir;
+        }
+        Input::Many(xs)
+    }
+}
+
+impl InputSource {
+    /// Creates a source from a path as [`Input::new`], saving a directory to the
+    /// subdirectory of its name, OR sources from each path matching a glob
+    /// pattern, such as `books/**/*.fb2`, keeping their layout relative to the
+    /// pattern base. Detects books by content if `sniff` is set.
+    ///
+    /// # Errors
+    /// Returns an error if the path is not a valid input, or if the pattern
+    /// matches no book or directory.
+    pub fn new(path: impl AsRef<Path>, sniff: bool) -> Result<Vec<InputSource>> {
+        let path = path.as_ref();
+        if !is_glob(path) || path.exists() {
+            let input = Input::new_with(path, sniff)?;
+            let subdir = dir_name(&input);
+            return Ok(vec![InputSource { subdir, input }]);
+        }
+
+        let (base, matched) = glob::glob(path)?;
+        let xs: Vec<InputSource> = matched
+            .into_iter()
+            .filter_map(|rel| {
+                let input = Input::new_with(base.join(&rel), sniff).ok()?;
+                Some(InputSource {
+                    subdir: layout_subdir(rel, &input),
+                    input,
+                })
+            })
+            .collect();
+
+        if xs.is_empty() {
+            return Err(Error::InvalidValue(format!(
+                "No book or directory matches '{}'",
+                path.display()
+            )));
+        }
+        Ok(xs)
+    }
+
+    /// Reads sources from a list of paths separated by NUL OR newlines, such as
+    /// an output of `find -print0`. A relative path keeps its layout, other
+    /// paths are saved as with [`InputSource::new`].
+    ///
+    /// # Errors
+    /// Returns an error if the list cannot be read or a path is not a valid
+    /// input.
+    pub fn read_list(list: impl AsRef<Path>, sniff: bool) -> Result<Vec<InputSource>> {
+        let list = list.as_ref();
+        let bytes = fs::read(list).map_err(|e| Error::from(e).with_path(list))?;
+        let text = String::from_utf8_lossy(&bytes);
+        let sep = if text.contains('\0') { '\0' } else { '\n' };
+
+        text.split(sep)
+            .map(|s| s.trim_end_matches('\r'))
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                let path = Path::new(s);
+                let input = Input::new_with(path, sniff)?;
+                let rel: Option<PathBuf> = path
+                    .components()
+                    .filter(|c| *c != Component::CurDir)
+                    .map(|c| match c {
+                        Component::Normal(x) => Some(x),
+                        _ => None,
+                    })
+                    .collect();
+                let subdir = match rel {
+                    Some(rel) => layout_subdir(rel, &input),
+                    None => dir_name(&input),
+                };
+                Ok(InputSource { subdir, input })
+            })
+            .collect()
+    }
+}
+
+/// Returns a name of the [`Input::Dir`] directory, OR an empty path.
+fn dir_name(input: &Input) -> Box<Path> {
+    match input {
+        Input::Dir(d) => d.file_name().map_or(Path::new(""), Path::new).into(),
+        _ => Path::new("").into(),
+    }
+}
+
+/// Returns a relative path of the [`Input::Dir`] directory, OR a parent of the
+/// relative path of the book.
+fn layout_subdir(rel: PathBuf, input: &Input) -> Box<Path> {
+    match input {
+        Input::Dir(_) => rel.into(),
+        _ => rel.parent().unwrap_or(Path::new("")).into(),
+    }
 }
 
 impl InputFileType {
-    pub(crate) const fn is_fb2(&self) -> bool {
-        matches!(self, Self::Fb2)
+    /// All types, in order of matching extensions: [`InputFileType::Zip`] is
+    /// the last, as `.fb2.zip` and `.zip` share the suffix.
+    pub const ALL: [InputFileType; 7] = [
+        InputFileType::Fb2,
+        InputFileType::Fb2Zip,
+        InputFileType::Fbz,
+        InputFileType::Fb2Gz,
+        InputFileType::Fb2Bz2,
+        InputFileType::Fb2Xz,
+        InputFileType::Zip,
+    ];
+
+    /// Detects a type of the file by content: checks zip, gzip, bzip2 and xz
+    /// magic bytes, then the XML declaration and the `FictionBook` root.
+    /// Returns `None` if the file is not a book.
+    ///
+    /// An extension of the same container is kept, such as `fbz`.
+    pub fn sniff(path: impl AsRef<Path>) -> Result<Option<InputFileType>> {
+        let path = path.as_ref();
+        sniff::sniff(path).map_err(|e| Error::from(e).with_path(path))
+    }
+
+    pub(crate) const fn is_zip(&self) -> bool {
+        matches!(self, Self::Zip)
+    }
+
+    const fn is_zip_container(&self) -> bool {
+        matches!(self, Self::Fb2Zip | Self::Fbz | Self::Zip)
+    }
+
+    /// Returns an extension without the leading dot, such as `fb2.gz`.
+    pub const fn as_extension(&self) -> &'static str {
+        match self {
+            Self::Fb2 => "fb2",
+            Self::Fb2Zip => "fb2.zip",
+            Self::Fbz => "fbz",
+            Self::Fb2Gz => "fb2.gz",
+            Self::Fb2Bz2 => "fb2.bz2",
+            Self::Fb2Xz => "fb2.xz",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+impl FromStr for InputFileType {
+    type Err = String;
+
+    /// Parses a book extension, such as `fb2.gz` or `.fbz`. A multi-book
+    /// [`InputFileType::Zip`] is not a book extension.
+    fn from_str(s: &str) -> std::result::Result<InputFileType, Self::Err> {
+        let ext = s.trim().trim_start_matches('.');
+        InputFileType::ALL
+            .into_iter()
+            .filter(|ty| !ty.is_zip())
+            .find(|ty| ty.as_extension().eq_ignore_ascii_case(ext))
+            .ok_or_else(|| format!("Unknown book extension '{}'", s))
     }
+}
 
-    pub(crate) const fn is_fb2_zip(&self) -> bool {
-        matches!(self, Self::Fb2Zip)
+/// Returns a type of a book detected by content, logging skipped files.
+fn sniff_logged(path: &Path) -> Option<InputFileType> {
+    match InputFileType::sniff(path) {
+        Ok(Some(ty)) => Some(ty),
+        Ok(None) => {
+            info!("{} '{}'. {}", Msg::NotABook, path.display(), Msg::Skipping);
+            None
+        }
+        Err(e) => {
+            warn!("{}. {}", e, Msg::Skipping);
+            None
+        }
     }
+}
 
-    pub(crate) const fn as_extension(&self) -> &'static str {
-        if self.is_fb2() { "fb2" } else { "fb2.zip" }
+/// Checks that an archive has an fb2 entry, so other archives in a directory,
+/// such as `photos.zip`, are skipped with a message.
+fn has_fb2_entry_logged(path: &Path) -> bool {
+    let has_fb2 = File::open(path)
+        .ok()
+        .and_then(|f| zip::ZipArchive::new(f).ok())
+        .is_some_and(|zip| zip.file_names().any(is_fb2_name));
+    if !has_fb2 {
+        info!(
+            "{} '{}'. {}",
+            Msg::NotFoundAnyFb2InArchive,
+            path.display(),
+            Msg::Skipping
+        );
     }
+    has_fb2
+}
+
+/// Returns `true` if a name of a file has the `.fb2` extension.
+pub(crate) fn is_fb2_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    let len = bytes.len();
+    len > 3 && bytes[len - 4..len].eq_ignore_ascii_case(b".fb2")
 }
 
 fn get_input_file_type(f: &Path) -> Option<InputFileType> {
     let bytes = f.as_os_str().as_encoded_bytes();
     let len = bytes.len();
 
-    for (ext, ty) in [
-        (".fb2", InputFileType::Fb2),
-        (".fb2.zip", InputFileType::Fb2Zip),
-    ] {
-        let ext = ext.as_bytes();
+    for ty in InputFileType::ALL {
+        let ext = ty.as_extension().as_bytes();
         let l = ext.len();
 
-        if l > len {
-            break;
+        if l >= len {
+            continue;
         }
-        if bytes[len - l..len].eq_ignore_ascii_case(ext) {
+        if bytes[len - l - 1] == b'.' && bytes[len - l..len].eq_ignore_ascii_case(ext) {
             return Some(ty);
         }
     }
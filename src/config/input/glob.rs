@@ -0,0 +1,127 @@
+use crate::{Error, Result};
+use lazy_regex::{Regex, regex::escape};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// A component of a glob pattern.
+enum Part {
+    /// `**`, matching any number of components.
+    AnyDepth,
+    /// A component with `*`, `?` and `[...]` wildcards.
+    Name(Regex),
+}
+
+/// Returns `true` if the path has `*`, `?` or `[` wildcards.
+pub(crate) fn is_glob(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+/// Returns a base of the pattern, the longest path without wildcards, and
+/// paths matching the pattern, relative to the base, sorted by name.
+///
+/// Drops a file in a matched directory, which is cleaned with the directory,
+/// and a path matched twice, such as via a symlink.
+pub(super) fn glob(pattern: &Path) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let mut base = PathBuf::new();
+    let mut parts: Vec<Part> = Vec::new();
+
+    for c in pattern.components() {
+        let s = c.as_os_str().to_string_lossy();
+        if parts.is_empty() && !is_glob(Path::new(&*s)) {
+            base.push(c);
+        } else if s == "**" {
+            parts.push(Part::AnyDepth);
+        } else {
+            let re = Regex::new(&to_regex(&s))
+                .map_err(|e| Error::InvalidValue(format!("{} in '{}'", e, s)))?;
+            parts.push(Part::Name(re));
+        }
+    }
+    if base.as_os_str().is_empty() {
+        base.push(".");
+    }
+
+    let max_depth = match parts.iter().any(|p| matches!(p, Part::AnyDepth)) {
+        true => usize::MAX,
+        false => parts.len(),
+    };
+    let matched: Vec<(PathBuf, PathBuf, bool)> = WalkDir::new(&base)
+        .min_depth(1)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(&base).ok()?;
+            let names: Vec<_> = rel.iter().map(|x| x.to_string_lossy()).collect();
+            if !is_match(&parts, &names) {
+                return None;
+            }
+            let canonical = fs::canonicalize(e.path()).unwrap_or_else(|_| e.path().into());
+            Some((rel.to_path_buf(), canonical, e.file_type().is_dir()))
+        })
+        .collect();
+
+    let dirs: HashSet<&Path> = matched
+        .iter()
+        .filter(|(_, _, is_dir)| *is_dir)
+        .map(|(_, canonical, _)| &**canonical)
+        .collect();
+    let mut seen: HashSet<&Path> = HashSet::new();
+    let matched = matched
+        .iter()
+        .filter(|(_, canonical, is_dir)| {
+            *is_dir || !canonical.parent().is_some_and(|p| dirs.contains(p))
+        })
+        .filter(|(_, canonical, _)| seen.insert(canonical))
+        .map(|(rel, _, _)| rel.clone())
+        .collect();
+
+    Ok((base, matched))
+}
+
+fn is_match(parts: &[Part], names: &[impl AsRef<str>]) -> bool {
+    match parts.split_first() {
+        None => names.is_empty(),
+        Some((Part::AnyDepth, rest)) => (0..=names.len()).any(|i| is_match(rest, &names[i..])),
+        Some((Part::Name(re), rest)) => names
+            .split_first()
+            .is_some_and(|(n, ns)| re.is_match(n.as_ref()) && is_match(rest, ns)),
+    }
+}
+
+/// Translates a component of a glob pattern to an anchored regex.
+fn to_regex(glob: &str) -> String {
+    let mut re = String::from(if cfg!(windows) { "(?i)^" } else { "^" });
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            '[' if chars.clone().any(|c| c == ']') => {
+                re.push('[');
+                if chars.next_if(|&c| c == '!' || c == '^').is_some() {
+                    re.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if matches!(c, '\\' | '[' | '&' | '~') {
+                        re.push('\\');
+                    }
+                    re.push(c);
+                }
+                re.push(']');
+            }
+            c => re.push_str(&escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    re.push('$');
+    re
+}
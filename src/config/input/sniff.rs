@@ -0,0 +1,120 @@
+use super::{InputFileType, get_input_file_type, is_fb2_name};
+use crate::xml_to_utf8::decode_xml;
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use lzma_rust2::XzReader;
+use quick_xml::{Reader, events::Event};
+use std::{
+    fs::File,
+    io::{self, Read, Seek},
+    path::Path,
+};
+use zip::ZipArchive;
+
+/// Length of a decompressed head checked for the FictionBook root, read again
+/// while the head ends before the root.
+const HEAD_LEN: u64 = 8192;
+
+/// Maximum length of a head, such as one with a long leading comment.
+const MAX_HEAD_LEN: usize = 65536;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const GZ_MAGIC: &[u8] = b"\x1f\x8b";
+const BZ2_MAGIC: &[u8] = b"BZh";
+const XZ_MAGIC: &[u8] = b"\xfd7zXZ\x00";
+
+pub(super) fn sniff(path: &Path) -> io::Result<Option<InputFileType>> {
+    let sniffed = sniff_reader(File::open(path)?)?;
+
+    // An extension of the same container is kept, such as `fbz`. A multi-book
+    // archive is always `zip`, as it is cleaned entry by entry.
+    Ok(sniffed.map(|ty| match get_input_file_type(path) {
+        Some(ext) if ext == ty => ext,
+        Some(ext) if ext.is_zip_container() && ty == InputFileType::Fb2Zip => ext,
+        _ => ty,
+    }))
+}
+
+pub(super) fn sniff_reader<R: Read + Seek + 'static>(
+    mut file: R,
+) -> io::Result<Option<InputFileType>> {
+    let mut magic = Vec::with_capacity(XZ_MAGIC.len());
+    (&mut file)
+        .take(XZ_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    file.rewind()?;
+
+    let sniffed = if magic.starts_with(ZIP_MAGIC) {
+        sniff_zip(file)
+    } else {
+        let (ty, reader): (_, Box<dyn Read>) = if magic.starts_with(GZ_MAGIC) {
+            (InputFileType::Fb2Gz, Box::new(MultiGzDecoder::new(file)))
+        } else if magic.starts_with(BZ2_MAGIC) {
+            (InputFileType::Fb2Bz2, Box::new(MultiBzDecoder::new(file)))
+        } else if magic.starts_with(XZ_MAGIC) {
+            (InputFileType::Fb2Xz, Box::new(XzReader::new(file, true)))
+        } else {
+            (InputFileType::Fb2, Box::new(file))
+        };
+        is_fb2_head(reader).then_some(ty)
+    };
+    Ok(sniffed)
+}
+
+/// Returns [`InputFileType::Fb2Zip`] for an archive with one fb2, or
+/// [`InputFileType::Zip`] for an archive with many, if the first fb2 has the
+/// `FictionBook` root.
+fn sniff_zip<R: Read + Seek>(file: R) -> Option<InputFileType> {
+    let mut zip = ZipArchive::new(file).ok()?;
+    let fb2s: Vec<usize> = (0..zip.len())
+        .filter(|&i| zip.name_for_index(i).is_some_and(is_fb2_name))
+        .collect();
+    if !is_fb2_head(zip.by_index(*fb2s.first()?).ok()?) {
+        return None;
+    }
+    match fb2s.len() {
+        1 => Some(InputFileType::Fb2Zip),
+        _ => Some(InputFileType::Zip),
+    }
+}
+
+/// Checks that a head of an XML has the `FictionBook` root, skipping the XML
+/// declaration, comments, processing instructions and DOCTYPE.
+fn is_fb2_head(mut reader: impl Read) -> bool {
+    let mut head = Vec::new();
+    loop {
+        // A decoder error only shortens the head.
+        let at_end = match (&mut reader).take(HEAD_LEN).read_to_end(&mut head) {
+            Ok(n) => (n as u64) < HEAD_LEN,
+            Err(_) => true,
+        };
+        match head_root_is_fb2(&head) {
+            Some(is_fb2) => return is_fb2,
+            None if at_end || head.len() >= MAX_HEAD_LEN => return false,
+            None => {}
+        }
+    }
+}
+
+/// Checks the root of a head, or returns `None` if the head ends before it.
+fn head_root_is_fb2(head: &[u8]) -> Option<bool> {
+    // A multi-byte char cut at the end is not a sign of another encoding.
+    let head = match std::str::from_utf8(head) {
+        Err(e) if e.error_len().is_none() => &head[..e.valid_up_to()],
+        _ => head,
+    };
+    let head = decode_xml(head).0;
+
+    let mut reader = Reader::from_reader(&*head);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e) | Event::Empty(e)) => {
+                return Some(e.local_name().as_ref() == b"FictionBook");
+            }
+            Ok(Event::Text(e)) if !e.iter().all(u8::is_ascii_whitespace) => return Some(false),
+            Ok(Event::Eof) | Err(_) => return None,
+            Ok(_) => buf.clear(),
+        }
+    }
+}
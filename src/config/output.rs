@@ -1,7 +1,8 @@
-use crate::{Input, Result};
+use crate::{Error, Input, Result};
 use std::{
-    env, fs,
+    env, fmt, fs,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 /// Output configuration.
@@ -13,6 +14,19 @@ pub struct Output {
     pub len_created_dir_chain: usize,
 }
 
+/// Style of output XML.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputStyle {
+    /// Write whitespace as is.
+    #[default]
+    Preserve,
+    /// Remove whitespace-only text between block tags.
+    Minify,
+    /// Remove whitespace-only text between block tags and indent them with
+    /// 2 spaces, ending the document with a newline.
+    Pretty,
+}
+
 impl Output {
     /// Creates non-exists directories in the directory chain up to [`Output::dir`],
     /// storing the count of created directories to [`Output::len_created_dir_chain`].
@@ -32,7 +46,7 @@ impl Output {
             if let Err(err) = fs::create_dir(dir) {
                 if !dir.exists() {
                     remove_created_dirs(&self.dir, dirs.len());
-                    return Err(err.into());
+                    return Err(Error::from(err).with_path(dir));
                 }
             }
         }
@@ -59,6 +73,7 @@ impl Output {
         match input {
             Input::Dir(d) => new(d),
             Input::File(f) => new(f.path.parent().unwrap_or(Path::new("."))),
+            Input::Stdin | Input::Many(_) => new(Path::new(".")),
         }
     }
 }
@@ -73,6 +88,39 @@ impl Default for Output {
     }
 }
 
+impl OutputStyle {
+    pub const ALL: [OutputStyle; 3] = [
+        OutputStyle::Preserve,
+        OutputStyle::Minify,
+        OutputStyle::Pretty,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OutputStyle::Preserve => "preserve",
+            OutputStyle::Minify => "minify",
+            OutputStyle::Pretty => "pretty",
+        }
+    }
+}
+
+impl FromStr for OutputStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<OutputStyle, Self::Err> {
+        OutputStyle::ALL
+            .into_iter()
+            .find(|x| x.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown output style '{}'", s))
+    }
+}
+
+impl fmt::Display for OutputStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 fn remove_created_dirs(mut dir: &Path, len_created_dir_chain: usize) {
     for _ in 0..len_created_dir_chain {
         let _ = fs::remove_dir(dir);
@@ -85,7 +133,7 @@ fn remove_created_dirs(mut dir: &Path, len_created_dir_chain: usize) {
 
 fn new_dir(path: &Path) -> Result<Box<Path>> {
     if path.is_file() {
-        Err("Is not a directory".into())
+        Err(Error::NotADirectory(path.into()))
     } else {
         let dir: PathBuf = try_absolutize(path.into())?.components().collect();
         Ok(ensure_long_path_prefix(dir).into())
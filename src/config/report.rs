@@ -0,0 +1,157 @@
+use crate::{Error, Removed};
+use std::{fmt, path::Path, str::FromStr, time::Duration};
+
+/// A report of [`crate::Config::run_report`], with one entry per book.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct Report {
+    pub books: Vec<BookReport>,
+    /// Error that stopped the run with [`crate::Config::exit_on_err`]. Books
+    /// are the ones processed before it.
+    pub error: Option<Error>,
+}
+
+/// A result of cleaning a book.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct BookReport {
+    /// Path of the input book.
+    pub src: Box<Path>,
+    /// Path of the output book. For [`BookStatus::Overwritten`], the path of
+    /// the overwritten book.
+    pub dest: Box<Path>,
+    pub status: BookStatus,
+    /// Size of the input book in bytes.
+    pub input_size: u64,
+    /// Size of the output book in bytes, if it is saved.
+    pub output_size: Option<u64>,
+    /// Counts of removed tags.
+    pub removed: Removed,
+    /// Size of the XML removed by the tag filter in bytes, before formatting
+    /// and compression.
+    pub removed_bytes: u64,
+    /// Duration of cleaning.
+    pub duration: Duration,
+    /// Count of FB2 entries of a multi-book archive failed to clean and kept
+    /// as is.
+    pub failed_entries: u64,
+    /// FB2 entries of a multi-book archive, in order of the archive. Empty for
+    /// other books.
+    pub entries: Vec<EntryReport>,
+    /// Error of a [`BookStatus::Failed`] book. Errors of entries are in
+    /// [`BookReport::entries`].
+    pub error: Option<Error>,
+}
+
+/// A result of cleaning an FB2 entry of a multi-book archive.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct EntryReport {
+    /// Name of the entry in the archive.
+    pub name: String,
+    /// [`BookStatus::Cleaned`], [`BookStatus::DryRun`] or [`BookStatus::Failed`]
+    /// for an entry kept as is.
+    pub status: BookStatus,
+    /// Counts of removed tags.
+    pub removed: Removed,
+    /// Size of the XML removed by the tag filter in bytes.
+    pub removed_bytes: u64,
+    /// Error of a [`BookStatus::Failed`] entry. An error of the entry stopping
+    /// the run with [`crate::Config::exit_on_err`] is in [`Report::error`].
+    pub error: Option<Error>,
+}
+
+/// A status of a [`BookReport`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum BookStatus {
+    /// Cleaned and saved to the output directory.
+    Cleaned,
+    /// Cleaned and saved, keeping entries of a multi-book archive failed to
+    /// clean as is. See [`BookReport::failed_entries`].
+    Partial,
+    /// Skipped, as the output book is already exists.
+    Skipped,
+    /// Failed to clean or to overwrite.
+    Failed,
+    /// Cleaned and overwrote the input book.
+    Overwritten,
+    /// Cleaned without saving, see [`crate::Config::dry_run`].
+    DryRun,
+}
+
+/// A format of the CLI output.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReportFormat {
+    /// Human-readable log lines.
+    #[default]
+    Text,
+    /// A JSON document with all books and a summary, logging to stderr.
+    Json,
+    /// A JSON line per book and a summary line, logging to stderr.
+    Ndjson,
+}
+
+impl Report {
+    /// Returns the count of books with the status.
+    pub fn count(&self, status: BookStatus) -> usize {
+        self.books.iter().filter(|b| b.status == status).count()
+    }
+
+    /// Returns counts of tags removed from all books.
+    pub fn removed(&self) -> Removed {
+        let mut removed = Removed::default();
+        for b in &self.books {
+            removed.extend(&b.removed);
+        }
+        removed
+    }
+
+    /// Returns the size of XML removed from all books in bytes.
+    pub fn removed_bytes(&self) -> u64 {
+        self.books.iter().map(|b| b.removed_bytes).sum()
+    }
+}
+
+impl BookStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BookStatus::Cleaned => "cleaned",
+            BookStatus::Partial => "partial",
+            BookStatus::Skipped => "skipped",
+            BookStatus::Failed => "failed",
+            BookStatus::Overwritten => "overwritten",
+            BookStatus::DryRun => "dry_run",
+        }
+    }
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 3] =
+        [ReportFormat::Text, ReportFormat::Json, ReportFormat::Ndjson];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportFormat::Text => "text",
+            ReportFormat::Json => "json",
+            ReportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<ReportFormat, Self::Err> {
+        ReportFormat::ALL
+            .into_iter()
+            .find(|x| x.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown output format '{}'", s))
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -1,16 +1,29 @@
-use super::Config;
-use crate::{Input, InputFile, InputFileType, Msg, Result, remove_xml_tags};
+use super::{
+    Config, Validation,
+    input::{ReadSeek, is_fb2_name},
+    report::{BookReport, BookStatus, EntryReport, Report},
+};
+use crate::{
+    Error, Input, InputFile, InputFileType, Msg, OutputStyle, Refs, Removed, Result, format_xml,
+    remove_xml_tags, remove_xml_tags_with_refs, repair_xml, scan_xml_refs, validate_fb2,
+    xml_to_utf8::decode_xml,
+};
+use bzip2::{read::MultiBzDecoder, write::BzEncoder};
 use either::Either;
+use flate2::{read::MultiGzDecoder, write::GzEncoder};
 use log::{error, info, warn};
-use quick_xml::{Reader, Writer};
+use lzma_rust2::{XzOptions, XzReader, XzWriter};
+use quick_xml::{NsReader, Writer};
 use rayon::prelude::*;
 use std::{
     borrow::Cow,
+    ffi::OsStr,
     fs::{self, File},
-    io::{BufRead, BufReader, BufWriter, Write},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, Write},
     iter,
     path::{Component, Path, PathBuf},
     sync::{Mutex, Once},
+    time::{Duration, Instant},
 };
 use walkdir::WalkDir;
 use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
@@ -18,32 +31,88 @@ use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
 impl Config {
     /// Run for current [`Config`].
     pub fn run(&self) -> Result<()> {
+        match self.run_report()?.error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Run for current [`Config`], returning a [`Report`] with one entry per book.
+    ///
+    /// An error stopping the run with [`Config::exit_on_err`] is returned in
+    /// [`Report::error`], along with books processed before it.
+    pub fn run_report(&self) -> Result<Report> {
+        self.run_report_with(|_| ())
+    }
+
+    /// Same as [`Config::run_report`], calling `on_book` with a report of each
+    /// book as soon as its status is final.
+    pub fn run_report_with(&self, on_book: impl Fn(&BookReport) + Sync) -> Result<Report> {
+        if self.stdout && matches!(self.input, Input::Dir(_) | Input::Many(_)) {
+            return Err(Error::InvalidValue(Msg::StdoutNeedsOneBook.to_string()));
+        }
+
         let is_found_any = Once::new();
         let it = Mutex::new(self.subdirs_src_iter());
+        let error = Mutex::new(None);
 
-        let src_dests: Vec<(InputFile, Dest)> = (0..self.jobs)
+        let mut books: Vec<Book> = (0..self.jobs)
             .into_par_iter()
-            .map(|_| job_src_dests(self, &is_found_any, &it))
-            .collect::<std::result::Result<Vec<_>, String>>()
-            .map(|vecs| vecs.into_iter().flatten().collect())?;
+            .flat_map(|_| job_books(self, &is_found_any, &it, &error, &on_book))
+            .collect();
+        let error = error.into_inner().unwrap_or_else(|e| e.into_inner());
 
-        if !src_dests.is_empty() {
-            force_overwrites(src_dests);
+        if error.is_none() && books.iter().any(|b| b.overwrite.is_some()) {
+            force_overwrites(&mut books);
+        }
+        for b in books.iter().filter(|b| b.overwrite.is_some()) {
+            on_book(&b.report);
         }
 
-        if !is_found_any.is_completed() {
-            if let Input::Dir(d) = &self.input {
-                return Err(
-                    format!("{} '{}'", Msg::NotFoundAnyBookInDirectory, d.display()).into(),
-                );
+        if error.is_none() && !is_found_any.is_completed() {
+            match &self.input {
+                Input::Dir(d) => return Err(Error::NoBookInDirectory(d.clone())),
+                Input::Stdin if InputFile::stdin()?.is_none() => {
+                    return Err(Error::NotABook(Path::new(InputFile::STDIN_PATH).into()));
+                }
+                _ => {}
             }
         }
 
-        Ok(())
+        let mut books: Vec<BookReport> = books.into_iter().map(|b| b.report).collect();
+        books.sort_by(|a, b| a.src.cmp(&b.src));
+        let report = Report { books, error };
+
+        if self.dry_run {
+            info!(
+                "\n{}: {}",
+                Msg::WouldRemoveTotal,
+                would_remove(report.removed_bytes(), &report.removed())
+            );
+        }
+        Ok(report)
     }
 
     fn subdirs_src_iter(&self) -> impl Iterator<Item = (Option<Vec<PathBuf>>, InputFile)> {
         match &self.input {
+            Input::Many(xs) => Either::Left(
+                xs.iter()
+                    .flat_map(|x| self.layout_src_iter(&x.input, Some(&x.subdir))),
+            ),
+            input => Either::Right(self.layout_src_iter(input, None)),
+        }
+    }
+
+    /// Returns books of the input with their subdirectories of the output
+    /// directory, starting with `subdir`.
+    fn layout_src_iter<'a>(
+        &'a self,
+        input: &'a Input,
+        subdir: Option<&Path>,
+    ) -> impl Iterator<Item = (Option<Vec<PathBuf>>, InputFile)> + 'a {
+        let subdirs: Option<Vec<PathBuf>> = subdir.map(|p| normal_components(p).collect());
+
+        match input {
             Input::Dir(d) if self.recursive != 0 => {
                 let it = WalkDir::new(d)
                     .max_depth(self.recursive as usize)
@@ -52,37 +121,68 @@ impl Config {
                     .filter(|e| e.file_type().is_dir())
                     .filter(|e| !e.path().starts_with(&*self.output.dir))
                     .map(move |e| {
-                        let subdirs: Vec<PathBuf> = e
-                            .path()
-                            .strip_prefix(d)
-                            .unwrap_or(Path::new(""))
-                            .components()
-                            .filter_map(|x| match x {
-                                Component::Normal(x) => Some(PathBuf::from(x)),
-                                _ => None,
-                            })
-                            .collect();
+                        let mut subdirs = subdirs.clone().unwrap_or_default();
+                        subdirs.extend(normal_components(
+                            e.path().strip_prefix(d).unwrap_or(Path::new("")),
+                        ));
 
                         (Some(subdirs), Input::Dir(e.into_path().into()))
                     })
-                    .flat_map(|(subdirs, d)| iter::repeat(subdirs).zip(d.iter()));
+                    .flat_map(|(subdirs, d)| iter::repeat(subdirs).zip(d.iter_with(self.sniff)));
 
                 Either::Left(it)
             }
-            _ => Either::Right(iter::repeat(None).zip(self.input.iter())),
+            _ => Either::Right(iter::repeat(subdirs).zip(input.iter_with(self.sniff))),
         }
     }
 }
 
-fn job_src_dests(
+/// Formats bytes and tags removed by a dry run, such as `10 bytes; binary: 1`.
+fn would_remove(bytes: u64, removed: &Removed) -> String {
+    let mut s = format!("{} {}", bytes, Msg::Bytes);
+    if !removed.tags.is_empty() {
+        s.push_str(&format!("; {}", removed));
+    }
+    s
+}
+
+/// Checks that a file name ends with `.` and the extension, ignoring ASCII case.
+fn has_extension(name: &OsStr, ext: &str) -> bool {
+    let name = name.as_encoded_bytes();
+    name.len()
+        .checked_sub(ext.len() + 1)
+        .is_some_and(|i| name[i] == b'.' && name[i + 1..].eq_ignore_ascii_case(ext.as_bytes()))
+}
+
+fn normal_components(path: &Path) -> impl Iterator<Item = PathBuf> {
+    path.components().filter_map(|x| match x {
+        Component::Normal(x) => Some(PathBuf::from(x)),
+        _ => None,
+    })
+}
+
+/// A processed book.
+struct Book {
+    report: BookReport,
+    /// Source and destination of a cleaned book to overwrite.
+    overwrite: Option<(InputFile, Dest)>,
+}
+
+fn job_books(
     cfg: &Config,
     is_found_any: &Once,
     it: &Mutex<impl Iterator<Item = (Option<Vec<PathBuf>>, InputFile)>>,
-) -> std::result::Result<Vec<(InputFile, Dest)>, String> {
-    let mut src_dests: Vec<(InputFile, Dest)> = Vec::new();
-    let mut zip_owner: Option<ZipArchive<File>> = None;
+    error: &Mutex<Option<Error>>,
+    on_book: &(impl Fn(&BookReport) + Sync),
+) -> Vec<Book> {
+    let mut books: Vec<Book> = Vec::new();
+    let mut zip_owner: Option<ZipArchive<Box<dyn ReadSeek>>> = None;
 
     loop {
+        // Another job is stopped by an error.
+        if error.lock().is_ok_and(|e| e.is_some()) {
+            break;
+        }
         let (src, dest) = {
             let mut it = it.lock().ok();
             match it.as_mut().and_then(|it| it.next()) {
@@ -98,54 +198,152 @@ fn job_src_dests(
         };
         info!("{} '{}'...", Msg::Cleaning, src.path.display());
 
-        if !cfg.force && dest.path.exists() {
+        let mut report = BookReport {
+            src: src.path.clone(),
+            dest: dest.path.clone().into(),
+            status: BookStatus::Skipped,
+            input_size: src.len(),
+            output_size: None,
+            removed: Removed::default(),
+            removed_bytes: 0,
+            duration: Duration::ZERO,
+            failed_entries: 0,
+            entries: Vec::new(),
+            error: None,
+        };
+
+        if !cfg.force && !cfg.stdout && dest.path.exists() {
             warn!(
                 "{} '{}'. {}",
                 Msg::FileIsAlreadyExists,
                 dest.path.display(),
                 Msg::Skipping,
             );
+            on_book(&report);
+            books.push(Book {
+                report,
+                overwrite: None,
+            });
             continue;
         }
 
-        match try_reader_writer(&mut zip_owner, &src, &dest)
-            .and_then(|(mut r, mut w)| remove_xml_tags(&mut r, &mut w, &cfg.tags))
-        {
-            Err(e) if cfg.exit_on_err => return Err(e.to_string()),
+        let start = Instant::now();
+        let res = clean_book(cfg, &mut zip_owner, &src, &dest).map_err(|e| e.with_path(&src.path));
+        report.duration = start.elapsed();
+
+        match res {
+            Err(e) if cfg.exit_on_err => {
+                if let Ok(mut error) = error.lock() {
+                    error.get_or_insert(e);
+                }
+                break;
+            }
+            Ok(cleaned) if cleaned.error.is_some() => {
+                // The archive with the entry stopping the run is reported.
+                report.status = BookStatus::Failed;
+                report.removed = cleaned.removed;
+                report.removed_bytes = cleaned.bytes;
+                report.failed_entries = cleaned.failed_entries;
+                report.entries = cleaned.entries;
+                on_book(&report);
+                books.push(Book {
+                    report,
+                    overwrite: None,
+                });
+                if let (Ok(mut error), Some(e)) = (error.lock(), cleaned.error) {
+                    error.get_or_insert(e);
+                }
+                break;
+            }
             Err(e) => {
                 error!("{}. {}", e, Msg::Skipping);
+                report.status = BookStatus::Failed;
+                report.error = Some(e);
+                on_book(&report);
+                books.push(Book {
+                    report,
+                    overwrite: None,
+                });
+                continue;
+            }
+            Ok(cleaned) if cfg.dry_run => {
+                info!(
+                    "{} '{}': {}",
+                    Msg::WouldRemoveFrom,
+                    src.path.display(),
+                    would_remove(cleaned.bytes, &cleaned.removed)
+                );
+                report.status = BookStatus::DryRun;
+                report.removed = cleaned.removed;
+                report.removed_bytes = cleaned.bytes;
+                report.failed_entries = cleaned.failed_entries;
+                report.entries = cleaned.entries;
+                on_book(&report);
+                books.push(Book {
+                    report,
+                    overwrite: None,
+                });
                 continue;
             }
-            Ok(()) => {
+            Ok(cleaned) => {
                 info!(
                     "{} '{}'",
                     Msg::SuccessCleanedAndSavedTo,
                     dest.path.display()
-                )
+                );
+                report.status = match cleaned.failed_entries {
+                    0 => BookStatus::Cleaned,
+                    _ => BookStatus::Partial,
+                };
+                if !cfg.stdout {
+                    report.output_size = fs::metadata(&dest.path).ok().map(|m| m.len());
+                }
+                report.removed = cleaned.removed;
+                report.removed_bytes = cleaned.bytes;
+                report.failed_entries = cleaned.failed_entries;
+                report.entries = cleaned.entries;
             }
         }
 
-        if cfg.force {
-            src_dests.push((src, dest));
+        let overwrite = (cfg.force && !cfg.stdout && !src.is_stdin()).then_some((src, dest));
+        // An overwritten book is reported after overwriting.
+        if overwrite.is_none() {
+            on_book(&report);
         }
+        books.push(Book { report, overwrite });
     }
-    Ok(src_dests)
+    books
 }
 
-fn force_overwrites(src_dests: Vec<(InputFile, Dest)>) {
+fn force_overwrites(books: &mut [Book]) {
     info!("\n{}...", Msg::OverwritingBooks);
 
-    for (src, dest) in &src_dests {
+    for book in books.iter_mut() {
+        let Some((src, dest)) = &book.overwrite else {
+            continue;
+        };
         info!("{} '{}'...", Msg::Overwriting, src.path.display());
-        match dest.force_overwrite(&src) {
-            Ok(()) => info!("{} '{}'", Msg::SuccessOverwritedFrom, dest.path.display()),
-            Err(e) => error!("{}: {}", Msg::Overwriting, e),
+        match dest.force_overwrite(src) {
+            Ok(path) => {
+                info!("{} '{}'", Msg::SuccessOverwritedFrom, dest.path.display());
+                book.report.status = BookStatus::Overwritten;
+                book.report.dest = path.into();
+            }
+            Err(e) => {
+                error!("{}: {}", Msg::Overwriting, e);
+                book.report.status = BookStatus::Failed;
+                book.report.error = Some(e);
+            }
         }
     }
 
     let mut dirs: Vec<PathBuf> = Vec::new();
-    for (_, dest) in src_dests {
-        for d in dest.created_dirs.into_iter().flat_map(|ds| ds.into_iter()) {
+    for book in books.iter_mut() {
+        let created_dirs = book
+            .overwrite
+            .as_mut()
+            .and_then(|(_, d)| d.created_dirs.take());
+        for d in created_dirs.into_iter().flat_map(|ds| ds.into_iter()) {
             dirs.push(d);
         }
     }
@@ -158,63 +356,400 @@ fn force_overwrites(src_dests: Vec<(InputFile, Dest)>) {
     }
 }
 
-fn try_reader_writer<'a>(
-    zip_owner: &'a mut Option<ZipArchive<File>>,
-    src: &'a InputFile,
-    dest: &'a Dest,
-) -> Result<(Reader<Box<dyn BufRead + 'a>>, Writer<Box<dyn Write + 'a>>)> {
-    let src_file = File::open(&src.path)?;
-    let dest_file = File::create(&dest.path)?;
-
-    let reader = match src.ty {
-        InputFileType::Fb2 => {
-            Reader::from_reader(Box::new(BufReader::new(src_file)) as Box<dyn BufRead>)
+/// Tags and bytes removed from a book by the tag filter.
+#[derive(Default)]
+struct Cleaned {
+    removed: Removed,
+    bytes: u64,
+    /// Entries of a multi-book archive failed to clean and kept as is.
+    failed_entries: u64,
+    entries: Vec<EntryReport>,
+    /// Error of an entry stopping the run with [`Config::exit_on_err`].
+    error: Option<Error>,
+}
+
+fn clean_book(
+    cfg: &Config,
+    zip_owner: &mut Option<ZipArchive<Box<dyn ReadSeek>>>,
+    src: &InputFile,
+    dest: &Dest,
+) -> Result<Cleaned> {
+    if src.ty.is_zip() {
+        return clean_archive(cfg, src, dest);
+    }
+    clean_source(cfg, Source::File(zip_owner, src), &src.path, || {
+        try_writer(dest)
+    })
+}
+
+/// Cleans each FB2 entry of a multi-book archive into an archive with the same
+/// layout, copying other entries as is.
+///
+/// Entries are cleaned one at a time by the job of the archive, regardless of
+/// [`Config::jobs`], each read into memory. An entry failed to clean is kept
+/// as is and counted in [`Cleaned::failed_entries`], or stops cleaning with
+/// [`Config::exit_on_err`], returned in [`Cleaned::error`].
+fn clean_archive(cfg: &Config, src: &InputFile, dest: &Dest) -> Result<Cleaned> {
+    let zip = ZipArchive::new(src.open()?)?;
+    if !zip.file_names().any(is_fb2_name) {
+        return Err(Error::NoFb2InArchive(src.path.clone()));
+    }
+
+    if cfg.dry_run {
+        clean_entries(cfg, src, zip, None::<ZipWriter<File>>)
+    } else if dest.is_stdout {
+        let writer = ZipWriter::new_stream(BufWriter::new(io::stdout()));
+        clean_entries(cfg, src, zip, Some(writer))
+    } else {
+        let writer = ZipWriter::new(BufWriter::new(create_file(&dest.path)?));
+        clean_entries(cfg, src, zip, Some(writer))
+    }
+}
+
+fn clean_entries<W: Write + Seek>(
+    cfg: &Config,
+    src: &InputFile,
+    mut zip: ZipArchive<Box<dyn ReadSeek>>,
+    mut writer: Option<ZipWriter<W>>,
+) -> Result<Cleaned> {
+    let mut cleaned = Cleaned::default();
+    let mut xml = Vec::<u8>::new();
+    let mut out = Vec::<u8>::new();
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        if !entry.is_file() || !is_fb2_name(entry.name()) {
+            if let Some(w) = writer.as_mut() {
+                w.raw_copy_file(entry)?;
+            }
+            continue;
         }
-        InputFileType::Fb2Zip => {
+
+        let path = src.path.join(entry.name());
+        info!("{} '{}'...", Msg::Cleaning, path.display());
+        let (name, options) = (entry.name().to_owned(), entry.options());
+        xml.clear();
+        entry.read_to_end(&mut xml)?;
+        drop(entry);
+
+        out.clear();
+        let mut report = EntryReport {
+            name: name.clone(),
+            status: BookStatus::Failed,
+            removed: Removed::default(),
+            removed_bytes: 0,
+            error: None,
+        };
+        match clean_entry(cfg, &xml, &path, &mut out) {
+            Ok(c) => {
+                cleaned.removed.extend(&c.removed);
+                cleaned.bytes += c.bytes;
+                report.status = match cfg.dry_run {
+                    true => BookStatus::DryRun,
+                    false => BookStatus::Cleaned,
+                };
+                report.removed = c.removed;
+                report.removed_bytes = c.bytes;
+                if let Some(w) = writer.as_mut() {
+                    w.start_file(name, options)?;
+                    w.write_all(&out)?;
+                }
+            }
+            Err(e) if cfg.exit_on_err => {
+                cleaned.failed_entries += 1;
+                cleaned.entries.push(report);
+                cleaned.error = Some(e);
+                return Ok(cleaned);
+            }
+            Err(e) => {
+                error!("{}. {}", e, Msg::KeepingOriginal);
+                cleaned.failed_entries += 1;
+                report.error = Some(e);
+                if let Some(w) = writer.as_mut() {
+                    w.raw_copy_file(zip.by_index(i)?)?;
+                }
+            }
+        }
+        cleaned.entries.push(report);
+    }
+
+    if let Some(w) = writer {
+        w.finish()?.flush()?;
+    }
+    Ok(cleaned)
+}
+
+fn clean_entry(cfg: &Config, xml: &[u8], path: &Path, out: &mut Vec<u8>) -> Result<Cleaned> {
+    clean_source(cfg, Source::Xml(xml), path, || Ok(out)).map_err(|e| e.with_path(path))
+}
+
+/// Cleans a book from the source, writing it to the lazily created destination.
+fn clean_source<W: Write>(
+    cfg: &Config,
+    mut source: Source<'_>,
+    path: &Path,
+    dest: impl FnOnce() -> Result<W>,
+) -> Result<Cleaned> {
+    // A book is decoded once, as each stage reads it anew.
+    let mut raw = Vec::new();
+    let decoded = if cfg.utf8 {
+        let xml = match &mut source {
+            Source::File(zip_owner, src) => {
+                open_book(zip_owner, src)?.read_to_end(&mut raw)?;
+                &raw[..]
+            }
+            Source::Xml(xml) => *xml,
+        };
+        Some(decode_book(xml, path))
+    } else {
+        None
+    };
+    let mut source = match &decoded {
+        Some(xml) => Source::Xml(xml),
+        None => source,
+    };
+
+    let repaired = if cfg.lenient {
+        Some(repair_book(&mut source.reader()?, path)?)
+    } else {
+        None
+    };
+    let mut source = match &repaired {
+        Some(xml) => Source::Xml(xml),
+        None => source,
+    };
+
+    let refs = if cfg.tags.needs_refs() {
+        Some(scan_xml_refs(&mut source.reader()?, &cfg.tags)?)
+    } else {
+        None
+    };
+
+    let mut reader = source.reader()?;
+
+    if cfg.dry_run {
+        return clean_xml(cfg, refs.as_ref(), &mut reader, io::sink()).map(|(c, _)| c);
+    }
+
+    #[cfg(feature = "images")]
+    let is_recompressed = cfg.images.is_enabled();
+    #[cfg(not(feature = "images"))]
+    let is_recompressed = false;
+
+    if !is_recompressed && cfg.style == OutputStyle::Preserve && cfg.validation == Validation::Off {
+        return clean_xml(cfg, refs.as_ref(), &mut reader, dest()?).map(|(c, _)| c);
+    }
+
+    // Next stages read an output of the previous one from memory.
+    let (cleaned, xml) = clean_xml(cfg, refs.as_ref(), &mut reader, Vec::<u8>::new())?;
+    let mut xml = Writer::new(xml);
+
+    #[cfg(feature = "images")]
+    if is_recompressed {
+        let src = xml.into_inner();
+        xml = Writer::new(Vec::new());
+        let mut reader = NsReader::from_reader(src.as_slice());
+        crate::recompress_xml_images(&mut reader, &mut xml, &cfg.images, cfg.tags.local_names)?;
+    }
+
+    if cfg.style != OutputStyle::Preserve {
+        let src = xml.into_inner();
+        xml = match cfg.style {
+            OutputStyle::Pretty => Writer::new_with_indent(Vec::new(), b' ', 2),
+            _ => Writer::new(Vec::new()),
+        };
+        format_xml(
+            &mut NsReader::from_reader(src.as_slice()),
+            &mut xml,
+            cfg.style,
+        )?;
+    }
+
+    let xml = xml.into_inner();
+    if cfg.validation != Validation::Off {
+        validate_book(cfg, path, &xml)?;
+    }
+    dest()?.write_all(&xml)?;
+    Ok(cleaned)
+}
+
+fn validate_book(cfg: &Config, path: &Path, xml: &[u8]) -> Result<()> {
+    let violations = validate_fb2(&mut NsReader::from_reader(xml))?;
+    for v in &violations {
+        warn!("{} '{}': {}", Msg::InvalidFb2, path.display(), v);
+    }
+
+    if cfg.validation == Validation::Fail && !violations.is_empty() {
+        return Err(Error::InvalidFb2(path.into()));
+    }
+    Ok(())
+}
+
+/// Cleans an XML into the destination, returning it with [`Cleaned`] counts.
+fn clean_xml<R: BufRead, W: Write>(
+    cfg: &Config,
+    refs: Option<&Refs>,
+    reader: &mut NsReader<R>,
+    dest: W,
+) -> Result<(Cleaned, W)> {
+    let mut writer = Writer::new(CountingWriter {
+        inner: dest,
+        len: 0,
+    });
+    let removed = match refs {
+        Some(refs) => remove_xml_tags_with_refs(reader, &mut writer, &cfg.tags, refs),
+        None => remove_xml_tags(reader, &mut writer, &cfg.tags),
+    }?;
+    let dest = writer.into_inner();
+    let bytes = reader.buffer_position().saturating_sub(dest.len);
+    let cleaned = Cleaned {
+        removed,
+        bytes,
+        ..Cleaned::default()
+    };
+    Ok((cleaned, dest.inner))
+}
+
+/// A writer counting written bytes.
+struct CountingWriter<W> {
+    inner: W,
+    len: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.len += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decodes a book to UTF-8, warning about a mis-declared encoding.
+fn decode_book<'a>(xml: &'a [u8], path: &Path) -> Cow<'a, [u8]> {
+    let (xml, misdeclared) = decode_xml(xml);
+    if let Some(m) = misdeclared {
+        warn!("{} '{}': {}", Msg::MisdeclaredEncoding, path.display(), m);
+    }
+    xml
+}
+
+fn repair_book<R: BufRead>(src: &mut NsReader<R>, path: &Path) -> Result<Vec<u8>> {
+    let mut xml = Writer::new(Vec::<u8>::new());
+    let repairs = repair_xml(src, &mut xml)?;
+    if !repairs.is_empty() {
+        warn!("{} '{}': {}", Msg::RepairedXml, path.display(), repairs);
+    }
+    Ok(xml.into_inner())
+}
+
+/// A book read anew by each stage of cleaning.
+enum Source<'a> {
+    File(&'a mut Option<ZipArchive<Box<dyn ReadSeek>>>, &'a InputFile),
+    /// An XML in memory, such as a repaired book or an archive entry.
+    Xml(&'a [u8]),
+}
+
+impl Source<'_> {
+    fn reader(&mut self) -> Result<NsReader<Box<dyn BufRead + '_>>> {
+        match self {
+            Source::File(zip_owner, src) => Ok(NsReader::from_reader(open_book(zip_owner, src)?)),
+            Source::Xml(xml) => Ok(NsReader::from_reader(Box::new(*xml))),
+        }
+    }
+}
+
+fn open_book<'a>(
+    zip_owner: &'a mut Option<ZipArchive<Box<dyn ReadSeek>>>,
+    src: &InputFile,
+) -> Result<Box<dyn BufRead + 'a>> {
+    let src_file = src.open()?;
+
+    let reader: Box<dyn BufRead> = match src.ty {
+        InputFileType::Fb2 => Box::new(BufReader::new(src_file)),
+        InputFileType::Fb2Gz => Box::new(BufReader::new(MultiGzDecoder::new(src_file))),
+        InputFileType::Fb2Bz2 => Box::new(BufReader::new(MultiBzDecoder::new(src_file))),
+        InputFileType::Fb2Xz => Box::new(BufReader::new(XzReader::new(src_file, true))),
+        InputFileType::Fb2Zip | InputFileType::Fbz | InputFileType::Zip => {
             *zip_owner = Some(ZipArchive::new(src_file)?);
             let zip = zip_owner.as_mut().unwrap();
             let fb2_index = (0..zip.len())
-                .find(|&i| {
-                    zip.by_index(i)
-                        .ok()
-                        .map(|f| {
-                            let bytes = f.name().as_bytes();
-                            let len = bytes.len();
-                            len > 3 && bytes[len - 4..len].eq_ignore_ascii_case(b".fb2")
-                        })
-                        .unwrap_or(false)
-                })
-                .ok_or_else(|| {
-                    format!("{} '{}'", Msg::NotFoundAnyFb2InArchive, src.path.display())
-                })?;
+                .find(|&i| zip.by_index(i).is_ok_and(|f| is_fb2_name(f.name())))
+                .ok_or_else(|| Error::NoFb2InArchive(src.path.clone()))?;
 
             let fb2_file = zip.by_index(fb2_index)?;
-            Reader::from_reader(Box::new(BufReader::new(fb2_file)) as Box<dyn BufRead>)
+            Box::new(BufReader::new(fb2_file))
         }
     };
 
-    let writer = match dest.ty {
-        InputFileType::Fb2 => Writer::new(Box::new(BufWriter::new(dest_file)) as Box<dyn Write>),
-        InputFileType::Fb2Zip => {
-            let mut zip_writer = ZipWriter::new(BufWriter::new(dest_file));
-            zip_writer.start_file(dest.zip_start_file(), SimpleFileOptions::default())?;
-            Writer::new(Box::new(zip_writer) as Box<dyn Write>)
+    Ok(reader)
+}
+
+fn try_writer(dest: &Dest) -> Result<Box<dyn Write>> {
+    let writer: Box<dyn Write> = match dest.ty {
+        InputFileType::Fb2Zip | InputFileType::Fbz if dest.is_stdout => {
+            let zip_writer = ZipWriter::new_stream(BufWriter::new(io::stdout()));
+            Box::new(start_zip_file(zip_writer, dest)?)
         }
+        InputFileType::Fb2Zip | InputFileType::Fbz => {
+            let zip_writer = ZipWriter::new(BufWriter::new(create_file(&dest.path)?));
+            Box::new(start_zip_file(zip_writer, dest)?)
+        }
+        InputFileType::Fb2Gz => Box::new(GzEncoder::new(
+            plain_writer(dest)?,
+            flate2::Compression::default(),
+        )),
+        InputFileType::Fb2Bz2 => Box::new(BzEncoder::new(
+            plain_writer(dest)?,
+            bzip2::Compression::default(),
+        )),
+        InputFileType::Fb2Xz => {
+            Box::new(XzWriter::new(plain_writer(dest)?, XzOptions::default())?.auto_finish())
+        }
+        InputFileType::Fb2 | InputFileType::Zip => plain_writer(dest)?,
     };
 
-    Ok((reader, writer))
+    Ok(writer)
+}
+
+fn start_zip_file<W: Write + Seek>(mut zip: ZipWriter<W>, dest: &Dest) -> Result<ZipWriter<W>> {
+    zip.start_file(dest.zip_start_file(), SimpleFileOptions::default())?;
+    Ok(zip)
+}
+
+/// Creates the destination file, or writes to stdout.
+fn plain_writer(dest: &Dest) -> Result<Box<dyn Write>> {
+    if dest.is_stdout {
+        Ok(Box::new(BufWriter::new(io::stdout())))
+    } else {
+        Ok(Box::new(BufWriter::new(create_file(&dest.path)?)))
+    }
+}
+
+fn create_file(path: &Path) -> Result<File> {
+    File::create(path).map_err(|e| Error::from(e).with_path(path))
 }
 
 struct Dest {
+    is_stdout: bool,
     created_dirs: Option<Vec<PathBuf>>,
     ty: InputFileType,
     stem: PathBuf,
     path: PathBuf,
+    /// Name of the FB2 entry of a zipped stdin, kept in the output archive.
+    entry: Option<String>,
 }
 
 impl Dest {
     fn new(cfg: &Config, subdirs: Option<Vec<PathBuf>>, src: &InputFile) -> Dest {
-        let ty = if cfg.zip {
+        let ty = if src.ty.is_zip() {
+            InputFileType::Zip
+        } else if let Some(ty) = cfg.container {
+            ty
+        } else if cfg.zip {
             InputFileType::Fb2Zip
         } else if cfg.unzip {
             InputFileType::Fb2
@@ -222,11 +757,15 @@ impl Dest {
             src.ty
         };
 
-        let stem = match src.path.file_stem() {
+        let stem = match src.path.file_name() {
+            _ if src.is_stdin() => PathBuf::from("stdin"),
             Some(os) => {
                 let mut p = PathBuf::from(os);
-                if src.ty.is_fb2_zip() {
-                    p.set_extension("");
+                // A sniffed type may differ from the extension, such as `War.gz`.
+                if has_extension(os, src.ty.as_extension()) {
+                    for _ in 0..=src.ty.as_extension().matches('.').count() {
+                        p.set_extension("");
+                    }
                 }
                 p
             }
@@ -239,6 +778,9 @@ impl Dest {
         if let Some(xs) = &subdirs {
             for x in xs {
                 path.push(x);
+                if cfg.dry_run {
+                    continue;
+                }
                 if let Ok(()) = fs::create_dir(&path) {
                     created_dirs
                         .get_or_insert_with(|| Vec::with_capacity(xs.len()))
@@ -247,32 +789,43 @@ impl Dest {
             }
         }
         path.push(&stem);
-        path.add_extension(ty.as_extension());
+        push_extension(&mut path, ty.as_extension());
+        if cfg.stdout {
+            path = PathBuf::from("-");
+        }
+
+        let entry = match src.ty {
+            InputFileType::Fb2Zip | InputFileType::Fbz if src.is_stdin() => fb2_entry_name(src),
+            _ => None,
+        };
 
         Dest {
+            is_stdout: cfg.stdout,
             created_dirs,
             ty,
             stem,
             path,
+            entry,
         }
     }
 
-    fn force_overwrite(&self, src: &InputFile) -> Result<()> {
+    /// Overwrites the input book, returning a path of the overwritten book.
+    fn force_overwrite(&self, src: &InputFile) -> Result<PathBuf> {
         let force_path = self.force_path(src);
 
-        if let Err(_) = fs::rename(&self.path, &force_path) {
+        if fs::rename(&self.path, &force_path).is_err() {
             fs::copy(&self.path, &force_path)?;
             if let Err(e) = fs::remove_file(&self.path) {
                 error!("{} '{}': {}", Msg::RemovingTempFile, self.path.display(), e);
             }
         }
 
-        if force_path != &*src.path {
+        if *force_path != *src.path {
             if let Err(e) = fs::remove_file(&src.path) {
                 error!("{} '{}': {}", Msg::RemovingInputFile, src.path.display(), e);
             }
         }
-        Ok(())
+        Ok(force_path.into_owned())
     }
 
     fn force_path<'a>(&self, src: &'a InputFile) -> Cow<'a, Path> {
@@ -285,13 +838,33 @@ impl Dest {
             None => PathBuf::from("."),
         };
         p.push(&self.stem);
-        p.add_extension(self.ty.as_extension());
+        push_extension(&mut p, self.ty.as_extension());
         Cow::Owned(p)
     }
 
     fn zip_start_file(&self) -> String {
+        if let Some(entry) = &self.entry {
+            return entry.clone();
+        }
         let mut s = self.stem.to_string_lossy().into_owned();
         s.push_str(".fb2");
         s
     }
 }
+
+/// Returns a name of the first FB2 entry of an archive.
+fn fb2_entry_name(src: &InputFile) -> Option<String> {
+    let zip = ZipArchive::new(src.open().ok()?).ok()?;
+    (0..zip.len())
+        .filter_map(|i| zip.name_for_index(i))
+        .find(|n| is_fb2_name(n))
+        .map(str::to_owned)
+}
+
+/// Appends `.ext` to a path (as `PathBuf::add_extension`, stable since 1.91).
+fn push_extension(path: &mut PathBuf, ext: &str) {
+    let mut os = std::mem::take(path).into_os_string();
+    os.push(".");
+    os.push(ext);
+    *path = os.into();
+}
@@ -1,63 +1,258 @@
+mod pis;
+mod selector;
+
+use crate::{Error, Result};
 use indexmap::IndexSet;
-use std::{ffi::OsStr, fmt, ops::Deref};
+use quick_xml::{
+    events::{BytesStart, attributes::Attribute},
+    name::NamespaceResolver,
+};
+use std::{ffi::OsStr, fmt, hash::Hash};
 
-/// Remove tags configuration.
-#[derive(Clone, Debug, PartialEq)]
-pub struct Tags(pub IndexSet<Box<[u8]>>);
+pub use pis::Pis;
+pub use selector::{AttrSelector, Selector, Step, StripAttr};
+pub(crate) use selector::{Element, Scope};
 
-impl Deref for Tags {
-    type Target = IndexSet<Box<[u8]>>;
+/// Tags configuration.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Tags {
+    /// Tags to remove OR to keep (see [`Tags::keep`]).
+    pub list: IndexSet<Selector>,
+    /// Tags to unwrap: remove start and end tags, keeping the content.
+    pub unwrap: IndexSet<Selector>,
+    /// Keep only the root and [`Tags::list`] tags, removing others.
+    pub keep: bool,
+    /// Match tags by local names only, ignoring namespaces.
+    pub local_names: bool,
+    /// Remove `image` tags referencing removed `binary` tags and `binary` tags
+    /// not referenced by kept `image` tags.
+    pub fix_refs: bool,
+    /// Keep `coverpage` with its `image` and the `binary` referenced by it,
+    /// removing other `image` and `binary` tags.
+    pub keep_cover: bool,
+    /// Remove `binary` tags with a decoded payload larger than the size in bytes
+    /// and `image` tags referencing them.
+    pub max_binary_size: Option<u64>,
+    /// Attributes to strip from kept tags.
+    pub strip_attrs: IndexSet<StripAttr>,
+    /// Tags to remove if they have no text (except whitespace) or tags after
+    /// the cleaning. Comments and processing instructions are content unless
+    /// stripped.
+    pub prune_empty: IndexSet<Selector>,
+    /// Strip comments.
+    pub strip_comments: bool,
+    /// Strip DOCTYPE.
+    pub strip_doctype: bool,
+    /// Handling of processing instructions.
+    pub pis: Pis,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+/// An action applied to a tag.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum TagAction {
+    Keep,
+    Remove,
+    Unwrap,
 }
 
 impl Tags {
-    pub(crate) fn new(os: impl AsRef<OsStr>) -> Tags {
-        let mut set: IndexSet<Box<[u8]>> = IndexSet::new();
-
-        let bytes = os.as_ref().as_encoded_bytes();
-        let len = bytes.len();
-        let mut i = 0;
-
-        for j in 0..=len {
-            if j == len || bytes[j] == b',' {
-                if i != j {
-                    set.insert(bytes[i..j].into());
-                }
-                i = j + 1;
+    /// Parses a comma-separated list of [`Selector`].
+    ///
+    /// # Errors
+    /// Returns an error if any selector syntax is invalid.
+    pub fn parse_list(os: impl AsRef<OsStr>) -> Result<IndexSet<Selector>> {
+        split_list(os.as_ref(), Selector::new)
+    }
+
+    /// Parses a comma-separated list of [`StripAttr`].
+    ///
+    /// # Errors
+    /// Returns an error if any attribute syntax is invalid.
+    pub fn parse_strip_attrs(os: impl AsRef<OsStr>) -> Result<IndexSet<StripAttr>> {
+        split_list(os.as_ref(), StripAttr::new)
+    }
+
+    /// Parses a size in bytes with an optional `K`, `M` or `G` suffix (powers
+    /// of 1024), such as `200K`.
+    ///
+    /// # Errors
+    /// Returns an error if the size is invalid.
+    pub fn parse_size(os: impl AsRef<OsStr>) -> Result<u64> {
+        let s = os.as_ref().to_string_lossy();
+        let s = s.trim();
+        let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+        let shift = match s[digits..].trim_start().to_ascii_uppercase().as_str() {
+            "" | "B" => 0,
+            "K" | "KB" | "KIB" => 10,
+            "M" | "MB" | "MIB" => 20,
+            "G" | "GB" | "GIB" => 30,
+            _ => {
+                return Err(Error::InvalidValue(format!(
+                    "Unknown size suffix in '{}'",
+                    s
+                )));
             }
+        };
+
+        let n: u64 = s[..digits]
+            .parse()
+            .map_err(|e| Error::InvalidValue(format!("{} in '{}'", e, s)))?;
+        n.checked_mul(1 << shift)
+            .ok_or_else(|| Error::InvalidValue(format!("Too large size '{}'", s)))
+    }
+
+    /// Returns `true` if cleaning needs [`Refs`] of a source scanned before.
+    ///
+    /// [`Refs`]: crate::Refs
+    pub fn needs_refs(&self) -> bool {
+        self.fix_refs || self.keep_cover || self.max_binary_size.is_some()
+    }
+
+    /// Returns a matching scope.
+    pub(crate) fn scope<'a>(&self, resolver: &'a NamespaceResolver) -> Scope<'a> {
+        Scope {
+            resolver,
+            local_names: self.local_names,
         }
+    }
+
+    /// Returns a start of a kept tag without [`Tags::strip_attrs`].
+    pub(crate) fn strip<'a>(
+        &self,
+        e: &'a Element,
+        ancestors: &[Element],
+        scope: &Scope,
+    ) -> BytesStart<'a> {
+        let is_stripped = |a: &Attribute| {
+            self.strip_attrs
+                .iter()
+                .any(|s| s.matches(a, e, ancestors, scope))
+        };
+        let attrs = || {
+            let mut attrs = e.start.attributes();
+            attrs.with_checks(false);
+            attrs.flatten()
+        };
 
-        Tags(set)
+        let mut start = e.start.borrow();
+        if !self.strip_attrs.is_empty() && attrs().any(|a| is_stripped(&a)) {
+            start.clear_attributes();
+            start.extend_attributes(attrs().filter(|a| !is_stripped(a)));
+        }
+        start
+    }
+
+    /// Returns `true` if a kept tag is removed when empty.
+    pub(crate) fn is_prunable(&self, e: &Element, ancestors: &[Element], scope: &Scope) -> bool {
+        self.prune_empty
+            .iter()
+            .any(|s| s.matches(e, ancestors, scope))
+    }
+
+    /// Returns an action for a tag with given ancestors (from the root).
+    pub(crate) fn action(&self, e: &Element, ancestors: &[Element], scope: &Scope) -> TagAction {
+        let is_root = ancestors.is_empty();
+        let contains =
+            |list: &IndexSet<Selector>| list.iter().any(|s| s.matches(e, ancestors, scope));
+
+        if self.keep {
+            if is_root || contains(&self.list) {
+                TagAction::Keep
+            } else if contains(&self.unwrap) {
+                TagAction::Unwrap
+            } else {
+                TagAction::Remove
+            }
+        } else if contains(&self.list) {
+            TagAction::Remove
+        } else if contains(&self.unwrap) {
+            TagAction::Unwrap
+        } else {
+            TagAction::Keep
+        }
     }
 }
 
 impl Default for Tags {
-    /// Returns new [`Tags`] with "binary", "coverpage" and "image" bytes.
+    /// Returns new [`Tags`] to remove "binary", "coverpage" and "image".
     /// ```
-    /// use fb2_clean::Tags;
+    /// use fb2_clean::{Pis, Tags};
     ///
     /// let tags = Tags::default();
-    /// assert_eq!(3, tags.len());
-    /// for t in ["binary", "coverpage", "image"] {
-    ///     assert!(tags.contains(t.as_bytes()));
-    /// }
+    /// assert!(!tags.keep);
+    /// assert!(tags.unwrap.is_empty());
+    /// assert!(!tags.local_names);
+    /// assert!(!tags.fix_refs);
+    /// assert!(!tags.keep_cover);
+    /// assert_eq!(None, tags.max_binary_size);
+    /// assert!(tags.strip_attrs.is_empty());
+    /// assert!(tags.prune_empty.is_empty());
+    /// assert!(!tags.strip_comments);
+    /// assert!(!tags.strip_doctype);
+    /// assert_eq!(Pis::Keep, tags.pis);
+    /// assert_eq!("binary,coverpage,image", tags.to_string());
     /// ```
     fn default() -> Tags {
-        let mut set: IndexSet<Box<[u8]>> = IndexSet::with_capacity(3);
-        set.insert(b"binary".as_slice().into());
-        set.insert(b"coverpage".as_slice().into());
-        set.insert(b"image".as_slice().into());
-        Tags(set)
+        let list = ["binary", "coverpage", "image"]
+            .into_iter()
+            .map(|t| Selector {
+                steps: Box::new([Step {
+                    name: t.as_bytes().into(),
+                    attrs: Box::default(),
+                    descendant: false,
+                }]),
+            })
+            .collect();
+
+        Tags {
+            list,
+            unwrap: IndexSet::new(),
+            keep: false,
+            local_names: false,
+            fix_refs: false,
+            keep_cover: false,
+            max_binary_size: None,
+            strip_attrs: IndexSet::new(),
+            prune_empty: IndexSet::new(),
+            strip_comments: false,
+            strip_doctype: false,
+            pis: Pis::Keep,
+        }
     }
 }
 
+/// Displays [`Tags::list`].
 impl fmt::Display for Tags {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", DisplayList(&self.list))
+    }
+}
+
+/// Splits a comma-separated list outside brackets, parsing its items.
+fn split_list<T: Eq + Hash>(os: &OsStr, parse: impl Fn(&[u8]) -> Result<T>) -> Result<IndexSet<T>> {
+    let mut list: IndexSet<T> = IndexSet::new();
+    let mut bytes = os.as_encoded_bytes();
+
+    while !bytes.is_empty() {
+        let j = selector::find_outside_brackets(bytes, b',').unwrap_or(bytes.len());
+        if !bytes[..j].trim_ascii().is_empty() {
+            list.insert(parse(&bytes[..j])?);
+        }
+        bytes = bytes.get(j + 1..).unwrap_or_default();
+    }
+
+    Ok(list)
+}
+
+/// Displays a list comma-separated.
+pub(crate) struct DisplayList<'a, T>(pub &'a IndexSet<T>);
+
+impl<T: fmt::Display> fmt::Display for DisplayList<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for (i, t) in (1..).zip(self.0.iter()) {
-            write!(f, "{}", String::from_utf8_lossy(t))?;
+            write!(f, "{}", t)?;
             if i < self.0.len() {
                 write!(f, ",")?;
             }
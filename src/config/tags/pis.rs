@@ -0,0 +1,58 @@
+use crate::{Error, Result};
+use lazy_regex::BytesRegex;
+use std::{ffi::OsStr, fmt};
+
+/// Handling of processing instructions (`<?target data?>`), except the XML
+/// declaration.
+#[derive(Clone, Debug, Default)]
+pub enum Pis {
+    /// Keep all.
+    #[default]
+    Keep,
+    /// Strip all.
+    Strip,
+    /// Keep only matching the pattern (`target data`), stripping others.
+    Matching(BytesRegex),
+}
+
+impl Pis {
+    /// Parses a pattern of [`Pis::Matching`].
+    ///
+    /// # Errors
+    /// Returns an error if the pattern is not a valid regex.
+    pub fn new_matching(os: impl AsRef<OsStr>) -> Result<Pis> {
+        let s = os.as_ref().to_string_lossy();
+        BytesRegex::new(&s)
+            .map(Pis::Matching)
+            .map_err(|e| Error::InvalidValue(e.to_string()))
+    }
+
+    /// Returns `true` if a processing instruction is kept.
+    pub(crate) fn keeps(&self, pi: &[u8]) -> bool {
+        match self {
+            Pis::Keep => true,
+            Pis::Strip => false,
+            Pis::Matching(re) => re.is_match(pi),
+        }
+    }
+}
+
+impl PartialEq for Pis {
+    fn eq(&self, other: &Pis) -> bool {
+        match (self, other) {
+            (Pis::Keep, Pis::Keep) | (Pis::Strip, Pis::Strip) => true,
+            (Pis::Matching(a), Pis::Matching(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Pis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pis::Keep => write!(f, "keep"),
+            Pis::Strip => write!(f, "strip"),
+            Pis::Matching(re) => write!(f, "{}", re.as_str()),
+        }
+    }
+}
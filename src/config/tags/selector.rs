@@ -0,0 +1,456 @@
+use crate::{Error, Result};
+use quick_xml::{
+    events::{BytesStart, attributes::Attribute},
+    name::{NamespaceResolver, QName, ResolveResult},
+};
+use std::{borrow::Cow, fmt};
+
+/// FictionBook 2 namespace.
+pub(crate) const FB2_NS: &[u8] = b"http://www.gribuser.ru/xml/fictionbook/2.0";
+/// XLink namespace.
+pub(crate) const XLINK_NS: &[u8] = b"http://www.w3.org/1999/xlink";
+/// XML namespace.
+const XML_NS: &[u8] = b"http://www.w3.org/XML/1998/namespace";
+
+/// A tag selector: a path of [`Step`] ending by a selected tag.
+///
+/// Syntax of a step: `name`, `name[attr]` (attribute presence) or `name[attr=value]`
+/// (attribute value). Conditions can be chained: `binary[id=cover][content-type]`.
+/// Values may be quoted by `"` or `'`.
+///
+/// Steps are separated by `/` (child) or `//` (descendant): `body/section/image`
+/// selects an image in a section of a body, `body//image` selects any image in a body.
+///
+/// Names are matched by namespace and local name. An unprefixed tag name matches
+/// the FictionBook namespace or no namespace, an unprefixed attribute name matches
+/// no namespace. Known prefixes are `fb` (FictionBook), `l` and `xlink` (XLink) and
+/// `xml`, independently of prefixes used in a book. Names with other prefixes are
+/// matched as is.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Selector {
+    pub steps: Box<[Step]>,
+}
+
+/// An attribute to strip from tags: `attr` (from all tags) or `selector@attr`
+/// (from tags matching the [`Selector`]), such as `style` or `p@id`.
+///
+/// An attribute may have a value condition: `section@class=ads`.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct StripAttr {
+    /// Tags to strip the attribute from. If `None`, strips from all tags.
+    pub tag: Option<Selector>,
+    pub attr: AttrSelector,
+}
+
+/// A step of a [`Selector`] path.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Step {
+    pub name: Box<[u8]>,
+    pub attrs: Box<[AttrSelector]>,
+    /// The step is a descendant (`//`), not a child (`/`), of the previous step.
+    pub descendant: bool,
+}
+
+/// An attribute condition of a [`Step`].
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct AttrSelector {
+    pub name: Box<[u8]>,
+    /// Expected value. If `None`, only presence is checked.
+    pub value: Option<Box<[u8]>>,
+}
+
+impl Selector {
+    /// Parses a selector.
+    ///
+    /// # Errors
+    /// Returns an error if the selector syntax is invalid.
+    pub fn new(s: &[u8]) -> Result<Selector> {
+        let s = s.trim_ascii();
+        let mut steps: Vec<Step> = Vec::new();
+        let mut descendant = false;
+        let mut rest = s;
+
+        loop {
+            let end = find_outside_brackets(rest, b'/').unwrap_or(rest.len());
+            let step = &rest[..end];
+
+            if step.is_empty() && end < rest.len() {
+                descendant = !steps.is_empty() || descendant;
+            } else {
+                steps.push(Step::new(step, descendant, s)?);
+                descendant = false;
+            }
+
+            match rest.get(end + 1..) {
+                Some(r) => rest = r,
+                None => break,
+            }
+        }
+
+        Ok(Selector {
+            steps: steps.into(),
+        })
+    }
+
+    /// Returns `true` if a tag with given ancestors (from the root) matches
+    /// the selector.
+    pub(crate) fn matches(&self, e: &Element, ancestors: &[Element], scope: &Scope) -> bool {
+        match self.steps.split_last() {
+            Some((target, steps)) => {
+                target.matches(e, scope)
+                    && matches_ancestors(steps, target.descendant, ancestors, scope)
+            }
+            None => false,
+        }
+    }
+}
+
+fn matches_ancestors(
+    steps: &[Step],
+    descendant: bool,
+    ancestors: &[Element],
+    scope: &Scope,
+) -> bool {
+    let Some((step, steps)) = steps.split_last() else {
+        return true;
+    };
+    let n = ancestors.len();
+
+    if descendant {
+        (0..n).rev().any(|i| {
+            step.matches(&ancestors[i], scope)
+                && matches_ancestors(steps, step.descendant, &ancestors[..i], scope)
+        })
+    } else {
+        n > 0
+            && step.matches(&ancestors[n - 1], scope)
+            && matches_ancestors(steps, step.descendant, &ancestors[..n - 1], scope)
+    }
+}
+
+/// A start tag with its resolved namespace.
+#[derive(Clone, Debug)]
+pub(crate) struct Element<'a> {
+    pub start: BytesStart<'a>,
+    pub ns: TagNs,
+}
+
+/// A resolved namespace of a tag.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum TagNs {
+    Unbound,
+    Bound(Box<[u8]>),
+    Unknown,
+}
+
+/// A matching scope.
+pub(crate) struct Scope<'a> {
+    pub resolver: &'a NamespaceResolver,
+    /// Match local names only, ignoring namespaces.
+    pub local_names: bool,
+}
+
+impl<'a> Element<'a> {
+    pub(crate) fn new(start: BytesStart<'a>, resolver: &NamespaceResolver) -> Element<'a> {
+        let ns = match resolver.resolve_element(start.name()).0 {
+            ResolveResult::Unbound => TagNs::Unbound,
+            ResolveResult::Bound(ns) => TagNs::Bound(ns.into_inner().into()),
+            ResolveResult::Unknown(_) => TagNs::Unknown,
+        };
+        Element { start, ns }
+    }
+
+    pub(crate) fn into_owned(self) -> Element<'static> {
+        Element {
+            start: self.start.into_owned(),
+            ns: self.ns,
+        }
+    }
+
+    /// Returns `true` if the tag is a FictionBook tag with the local name.
+    pub(crate) fn is_fb2(&self, local: &[u8], local_names: bool) -> bool {
+        self.start.local_name().into_inner() == local && (local_names || self.is_fb2_ns())
+    }
+
+    /// Returns `true` if the tag is in the FictionBook namespace or unbound.
+    pub(crate) fn is_fb2_ns(&self) -> bool {
+        match &self.ns {
+            TagNs::Unbound => true,
+            TagNs::Bound(ns) => **ns == *FB2_NS,
+            TagNs::Unknown => false,
+        }
+    }
+
+    /// Returns a value of the unprefixed `id` attribute.
+    pub(crate) fn id(&self) -> Option<Cow<'_, [u8]>> {
+        self.start
+            .try_get_attribute("id")
+            .ok()
+            .flatten()
+            .map(|a| a.value)
+    }
+
+    /// Returns a value of the XLink `href` attribute.
+    pub(crate) fn href(&self, scope: &Scope) -> Option<Cow<'_, [u8]>> {
+        self.start
+            .attributes()
+            .with_checks(false)
+            .flatten()
+            .find(|a| {
+                if scope.local_names {
+                    a.key.local_name().into_inner() == b"href"
+                } else {
+                    let (res, local) = scope.resolver.resolve_attribute(a.key);
+                    local.into_inner() == b"href"
+                        && matches!(res, ResolveResult::Bound(ns) if ns.into_inner() == XLINK_NS)
+                }
+            })
+            .map(|a| a.value)
+    }
+}
+
+/// Splits a qualified name to a known namespace and a local name.
+///
+/// Returns `None` if the name has an unknown prefix.
+fn split_name(name: &[u8]) -> Option<(Option<&'static [u8]>, &[u8])> {
+    let qname = QName(name);
+    let local = qname.local_name().into_inner();
+    match qname.prefix().map(|p| p.into_inner()) {
+        None => Some((None, local)),
+        Some(b"fb") => Some((Some(FB2_NS), local)),
+        Some(b"l" | b"xlink") => Some((Some(XLINK_NS), local)),
+        Some(b"xml") => Some((Some(XML_NS), local)),
+        Some(_) => None,
+    }
+}
+
+impl StripAttr {
+    /// Parses an attribute to strip.
+    ///
+    /// # Errors
+    /// Returns an error if the syntax is invalid.
+    pub fn new(s: &[u8]) -> Result<StripAttr> {
+        let s = s.trim_ascii();
+        let (tag, attr) = match find_outside_brackets(s, b'@') {
+            Some(i) => (Some(Selector::new(&s[..i])?), &s[i + 1..]),
+            None => (None, s),
+        };
+
+        let attr = AttrSelector::new(attr.trim_ascii(), s)?;
+        if attr.name.iter().any(|b| b"[]/@".contains(b)) {
+            return Err(Error::InvalidValue(format!(
+                "Invalid attribute name in '{}'",
+                String::from_utf8_lossy(s)
+            )));
+        }
+
+        Ok(StripAttr { tag, attr })
+    }
+
+    /// Returns `true` if the attribute of a tag with given ancestors (from the root)
+    /// should be stripped.
+    pub(crate) fn matches(
+        &self,
+        attr: &Attribute,
+        e: &Element,
+        ancestors: &[Element],
+        scope: &Scope,
+    ) -> bool {
+        self.attr.matches_attr(attr, scope)
+            && self
+                .tag
+                .as_ref()
+                .is_none_or(|t| t.matches(e, ancestors, scope))
+    }
+}
+
+impl Step {
+    fn new(s: &[u8], descendant: bool, selector: &[u8]) -> Result<Step> {
+        let s = s.trim_ascii();
+        let end = s.iter().position(|&b| b == b'[').unwrap_or(s.len());
+        let name = s[..end].trim_ascii();
+
+        if name.is_empty() {
+            return Err(Error::InvalidValue(format!(
+                "Empty tag name in '{}'",
+                String::from_utf8_lossy(selector)
+            )));
+        }
+
+        let mut attrs: Vec<AttrSelector> = Vec::new();
+        let mut rest = &s[end..];
+
+        while let Some(r) = rest.strip_prefix(b"[") {
+            let close = find_unquoted(r, b']').ok_or_else(|| {
+                Error::InvalidValue(format!(
+                    "Unclosed '[' in '{}'",
+                    String::from_utf8_lossy(selector)
+                ))
+            })?;
+            attrs.push(AttrSelector::new(&r[..close], selector)?);
+            rest = r[close + 1..].trim_ascii_start();
+        }
+
+        if !rest.is_empty() {
+            return Err(Error::InvalidValue(format!(
+                "Unexpected '{}' in '{}'",
+                String::from_utf8_lossy(rest),
+                String::from_utf8_lossy(selector)
+            )));
+        }
+
+        Ok(Step {
+            name: name.into(),
+            attrs: attrs.into(),
+            descendant,
+        })
+    }
+
+    fn matches(&self, e: &Element, scope: &Scope) -> bool {
+        self.matches_name(e, scope) && self.attrs.iter().all(|a| a.matches(&e.start, scope))
+    }
+
+    fn matches_name(&self, e: &Element, scope: &Scope) -> bool {
+        let qname = e.start.name();
+        let local = qname.local_name().into_inner();
+
+        if scope.local_names {
+            return QName(&self.name).local_name().into_inner() == local;
+        }
+
+        match split_name(&self.name) {
+            Some((None, l)) => l == local && e.is_fb2_ns(),
+            Some((Some(ns), l)) => l == local && matches!(&e.ns, TagNs::Bound(n) if **n == *ns),
+            None => *self.name == *qname.into_inner(),
+        }
+    }
+}
+
+impl AttrSelector {
+    fn new(s: &[u8], selector: &[u8]) -> Result<AttrSelector> {
+        let (name, value) = match s.iter().position(|&b| b == b'=') {
+            Some(i) => (&s[..i], Some(unquote(s[i + 1..].trim_ascii()))),
+            None => (s, None),
+        };
+        let name = name.trim_ascii();
+
+        if name.is_empty() {
+            return Err(Error::InvalidValue(format!(
+                "Empty attribute name in '{}'",
+                String::from_utf8_lossy(selector)
+            )));
+        }
+
+        Ok(AttrSelector {
+            name: name.into(),
+            value: value.map(Into::into),
+        })
+    }
+
+    fn matches(&self, e: &BytesStart, scope: &Scope) -> bool {
+        e.attributes()
+            .with_checks(false)
+            .flatten()
+            .any(|attr| self.matches_attr(&attr, scope))
+    }
+
+    fn matches_attr(&self, attr: &Attribute, scope: &Scope) -> bool {
+        let is_name = if scope.local_names {
+            QName(&self.name).local_name() == attr.key.local_name()
+        } else {
+            match split_name(&self.name) {
+                Some((None, _)) | None => *self.name == *attr.key.into_inner(),
+                Some((Some(ns), l)) => {
+                    let (res, local) = scope.resolver.resolve_attribute(attr.key);
+                    local.into_inner() == l
+                        && matches!(res, ResolveResult::Bound(n) if n.into_inner() == ns)
+                }
+            }
+        };
+        is_name && self.value.as_ref().is_none_or(|v| **v == *attr.value)
+    }
+}
+
+/// Returns the first index of a byte outside `[...]`.
+pub(super) fn find_outside_brackets(s: &[u8], byte: u8) -> Option<usize> {
+    let mut i = 0;
+    while i < s.len() {
+        match s[i] {
+            b if b == byte => return Some(i),
+            b'[' => i += find_unquoted(&s[i..], b']')?,
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Returns the first index of a byte outside quotes.
+fn find_unquoted(s: &[u8], byte: u8) -> Option<usize> {
+    let mut quote: Option<u8> = None;
+
+    for (i, &b) in s.iter().enumerate() {
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => (),
+            None if b == byte => return Some(i),
+            None if matches!(b, b'"' | b'\'') => quote = Some(b),
+            None => (),
+        }
+    }
+
+    None
+}
+
+fn unquote(s: &[u8]) -> &[u8] {
+    match s {
+        [q @ (b'"' | b'\''), inner @ .., last] if q == last => inner,
+        _ => s,
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", if step.descendant { "//" } else { "/" })?;
+            }
+            write!(f, "{}", step)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.name))?;
+        for a in &self.attrs {
+            write!(f, "[{}]", a)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AttrSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.name))?;
+        if let Some(v) = &self.value {
+            let v = String::from_utf8_lossy(v);
+            if v.contains([']', ',']) {
+                write!(f, "=\"{}\"", v)?;
+            } else {
+                write!(f, "={}", v)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for StripAttr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(t) = &self.tag {
+            write!(f, "{}@", t)?;
+        }
+        write!(f, "{}", self.attr)
+    }
+}
@@ -0,0 +1,42 @@
+use std::{fmt, str::FromStr};
+
+/// Validation of output books against the FictionBook schema.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Validation {
+    /// Do not validate.
+    #[default]
+    Off,
+    /// Log violations.
+    Warn,
+    /// Log violations and fail invalid books.
+    Fail,
+}
+
+impl Validation {
+    pub const ALL: [Validation; 3] = [Validation::Off, Validation::Warn, Validation::Fail];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Validation::Off => "off",
+            Validation::Warn => "warn",
+            Validation::Fail => "fail",
+        }
+    }
+}
+
+impl FromStr for Validation {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Validation, Self::Err> {
+        Validation::ALL
+            .into_iter()
+            .find(|x| x.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown validation mode '{}'", s))
+    }
+}
+
+impl fmt::Display for Validation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,157 @@
+use crate::Msg;
+use std::{error, fmt, io, path::Path};
+use zip::result::ZipError;
+
+/// An error of cleaning books.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An I/O error, with a path of the file if known.
+    Io {
+        path: Option<Box<Path>>,
+        source: io::Error,
+    },
+    /// A zip archive error, with a path of the archive if known.
+    Zip {
+        path: Option<Box<Path>>,
+        source: ZipError,
+    },
+    /// An XML error at the byte position, with a path of the book if known.
+    Xml {
+        path: Option<Box<Path>>,
+        position: u64,
+        source: quick_xml::Error,
+    },
+    /// A zip archive has no fb2 file.
+    NoFb2InArchive(Box<Path>),
+    /// An input directory has no book.
+    NoBookInDirectory(Box<Path>),
+    /// An input file has an unsupported extension.
+    UnsupportedExtension(Box<Path>),
+    /// An input file is not a book, see [`crate::InputFileType::sniff`].
+    NotABook(Box<Path>),
+    /// An output path is not a directory.
+    NotADirectory(Box<Path>),
+    /// An output book is not valid FB2, see [`crate::Validation::Fail`].
+    InvalidFb2(Box<Path>),
+    /// An invalid value, such as a tag list or a size.
+    InvalidValue(String),
+}
+
+impl Error {
+    pub(crate) fn xml(source: quick_xml::Error, position: u64) -> Error {
+        Error::Xml {
+            path: None,
+            position,
+            source,
+        }
+    }
+
+    /// Returns a path of the file the error relates to, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::Io { path, .. } | Error::Zip { path, .. } | Error::Xml { path, .. } => {
+                path.as_deref()
+            }
+            Error::NoFb2InArchive(p)
+            | Error::NoBookInDirectory(p)
+            | Error::UnsupportedExtension(p)
+            | Error::NotABook(p)
+            | Error::NotADirectory(p)
+            | Error::InvalidFb2(p) => Some(p),
+            Error::InvalidValue(_) => None,
+        }
+    }
+
+    /// Returns a stable name of the variant, such as `io` or `no_fb2_in_archive`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Io { .. } => "io",
+            Error::Zip { .. } => "zip",
+            Error::Xml { .. } => "xml",
+            Error::NoFb2InArchive(_) => "no_fb2_in_archive",
+            Error::NoBookInDirectory(_) => "no_book_in_directory",
+            Error::UnsupportedExtension(_) => "unsupported_extension",
+            Error::NotABook(_) => "not_a_book",
+            Error::NotADirectory(_) => "not_a_directory",
+            Error::InvalidFb2(_) => "invalid_fb2",
+            Error::InvalidValue(_) => "invalid_value",
+        }
+    }
+
+    /// Sets a path of the file the error relates to, if it is not set.
+    pub(crate) fn with_path(mut self, p: &Path) -> Error {
+        if let Error::Io { path, .. } | Error::Zip { path, .. } | Error::Xml { path, .. } =
+            &mut self
+        {
+            path.get_or_insert_with(|| p.into());
+        }
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => {
+                write_path(f, path)?;
+                write!(f, "{}", source)
+            }
+            Error::Zip { path, source } => {
+                write_path(f, path)?;
+                write!(f, "{}", source)
+            }
+            Error::Xml {
+                path,
+                position,
+                source,
+            } => {
+                write_path(f, path)?;
+                write!(f, "{} ({} {})", source, Msg::AtPosition, position)
+            }
+            Error::NoFb2InArchive(p) => {
+                write!(f, "{} '{}'", Msg::NotFoundAnyFb2InArchive, p.display())
+            }
+            Error::NoBookInDirectory(p) => {
+                write!(f, "{} '{}'", Msg::NotFoundAnyBookInDirectory, p.display())
+            }
+            Error::UnsupportedExtension(p) => {
+                write!(f, "File has unsupported extension '{}'", p.display())
+            }
+            Error::NotABook(p) => write!(f, "{} '{}'", Msg::NotABook, p.display()),
+            Error::NotADirectory(p) => write!(f, "Is not a directory '{}'", p.display()),
+            Error::InvalidFb2(p) => write!(f, "{} '{}'", Msg::InvalidFb2, p.display()),
+            Error::InvalidValue(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Zip { source, .. } => Some(source),
+            Error::Xml { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Error {
+        Error::Io { path: None, source }
+    }
+}
+
+impl From<ZipError> for Error {
+    fn from(source: ZipError) -> Error {
+        Error::Zip { path: None, source }
+    }
+}
+
+fn write_path(f: &mut fmt::Formatter<'_>, path: &Option<Box<Path>>) -> fmt::Result {
+    match path {
+        Some(p) => write!(f, "'{}': ", p.display()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{Error, OutputStyle, Result, config::tags::Element};
+use quick_xml::{
+    NsReader, Writer,
+    events::{BytesStart, BytesText, Event},
+};
+use std::io::{BufRead, Write};
+
+/// FictionBook tags with mixed content, where whitespace is significant.
+const MIXED: [&[u8]; 14] = [
+    b"p",
+    b"v",
+    b"subtitle",
+    b"text-author",
+    b"th",
+    b"td",
+    b"emphasis",
+    b"strong",
+    b"style",
+    b"a",
+    b"strikethrough",
+    b"sub",
+    b"sup",
+    b"code",
+];
+
+/// Writes an XML in the [`OutputStyle`].
+///
+/// Whitespace-only text in mixed content tags (such as `p` or `emphasis`) is
+/// always kept. For [`OutputStyle::Pretty`], `dest` should be created by
+/// [`Writer::new_with_indent`] with 2 spaces; mixed content tags are written
+/// without indents, and the document ends with a newline.
+pub fn format_xml<R, W>(
+    src: &mut NsReader<R>,
+    dest: &mut Writer<W>,
+    style: OutputStyle,
+) -> Result<()>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut buf = Vec::<u8>::new();
+    // Depth in mixed content tags.
+    let mut mixed_depth = 0usize;
+
+    loop {
+        let event = match src.read_event_into(&mut buf) {
+            Ok(Event::Eof) => break,
+            Ok(event) => event,
+            Err(err) => return Err(Error::xml(err, src.error_position())),
+        };
+
+        match event {
+            _ if style == OutputStyle::Preserve => dest.write_event(event)?,
+            Event::Text(e) if mixed_depth == 0 && e.iter().all(u8::is_ascii_whitespace) => (),
+            Event::Start(e) if mixed_depth > 0 => {
+                mixed_depth += 1;
+                write_inline(dest, Event::Start(e))?;
+            }
+            Event::Start(e) => {
+                if is_mixed(&e, src) {
+                    mixed_depth = 1;
+                }
+                dest.write_event(Event::Start(e))?;
+            }
+            Event::End(e) if mixed_depth > 1 => {
+                mixed_depth -= 1;
+                write_inline(dest, Event::End(e))?;
+            }
+            Event::End(e) => {
+                if mixed_depth == 1 {
+                    mixed_depth = 0;
+                    // Prevents an indent before the end.
+                    dest.write_event(Event::Text(BytesText::new("")))?;
+                }
+                dest.write_event(Event::End(e))?;
+            }
+            event if mixed_depth > 0 => write_inline(dest, event)?,
+            event => dest.write_event(event)?,
+        }
+        buf.clear();
+    }
+
+    if style == OutputStyle::Pretty {
+        dest.get_mut().write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn is_mixed<R>(e: &BytesStart, src: &NsReader<R>) -> bool {
+    let e = Element::new(e.borrow(), src.resolver());
+    MIXED.iter().any(|name| e.is_fb2(name, false))
+}
+
+/// Writes an event without an indent.
+fn write_inline<W: Write>(dest: &mut Writer<W>, event: Event) -> Result<()> {
+    Writer::new(dest.get_mut()).write_event(event)?;
+    Ok(())
+}
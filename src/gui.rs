@@ -1,10 +1,14 @@
 mod logger;
 
-use crate::{Config, Input, InputFile, Lang, Msg, Output, Result, Tags, msg};
+use crate::{
+    Config, Input, InputFile, InputFileType, Lang, Msg, Output, OutputStyle, Pis, Result, Tags,
+    Validation, config::tags::DisplayList, msg,
+};
 use eframe::egui;
+use indexmap::IndexSet;
 use log::{error, info};
 use logger::{GuiLog, GuiLogger};
-use std::path::Path;
+use std::{fmt, hash::Hash, path::Path};
 
 #[derive(Debug)]
 pub struct App {
@@ -14,6 +18,10 @@ pub struct App {
     output_buf: String,
     is_output_set: bool,
     tags_buf: String,
+    unwrap_tags_buf: String,
+    strip_attrs_buf: String,
+    prune_empty_buf: String,
+    max_binary_size_buf: String,
     lang: Lang,
 }
 
@@ -26,6 +34,10 @@ impl Default for App {
             output_buf: cfg.output.dir.display().to_string(),
             is_output_set: false,
             tags_buf: cfg.tags.to_string(),
+            unwrap_tags_buf: DisplayList(&cfg.tags.unwrap).to_string(),
+            strip_attrs_buf: DisplayList(&cfg.tags.strip_attrs).to_string(),
+            prune_empty_buf: DisplayList(&cfg.tags.prune_empty).to_string(),
+            max_binary_size_buf: String::new(),
             cfg,
             lang: Default::default(),
         }
@@ -122,15 +134,101 @@ impl eframe::App for App {
             });
             ui.add_space(10.0);
 
-            ui.label(msg!(GuiRemoveTags)).on_hover_text(msg!(HelpTags));
-            if ui.text_edit_singleline(&mut self.tags_buf).lost_focus() {
-                let new_tags = Tags::new(&self.tags_buf);
-                self.tags_buf = new_tags.to_string();
-                if new_tags != self.cfg.tags {
-                    self.cfg.tags = new_tags;
-                    info!("{}: '{}'", Msg::GuiTagsSet, self.tags_buf);
+            ui.horizontal(|ui| {
+                ui.label(msg!(GuiRemoveTags)).on_hover_text(msg!(HelpTags));
+                ui.checkbox(&mut self.cfg.tags.keep, msg!(GuiKeepOnlyTags))
+                    .on_hover_text(msg!(HelpKeep));
+            });
+            let tags = &mut self.cfg.tags;
+            list_field(
+                ui,
+                &mut self.tags_buf,
+                &mut tags.list,
+                |s| Tags::parse_list(s),
+                (Msg::GuiTagsSet, Msg::GuiErrorSetTags),
+            );
+
+            ui.label(msg!(GuiUnwrapTags))
+                .on_hover_text(msg!(HelpUnwrapTags));
+            list_field(
+                ui,
+                &mut self.unwrap_tags_buf,
+                &mut tags.unwrap,
+                |s| Tags::parse_list(s),
+                (Msg::GuiUnwrapTagsSet, Msg::GuiErrorSetTags),
+            );
+
+            ui.label(msg!(GuiStripAttrs))
+                .on_hover_text(msg!(HelpStripAttrs));
+            list_field(
+                ui,
+                &mut self.strip_attrs_buf,
+                &mut tags.strip_attrs,
+                |s| Tags::parse_strip_attrs(s),
+                (Msg::GuiStripAttrsSet, Msg::GuiErrorSetStripAttrs),
+            );
+
+            ui.label(msg!(GuiPruneEmpty))
+                .on_hover_text(msg!(HelpPruneEmpty));
+            list_field(
+                ui,
+                &mut self.prune_empty_buf,
+                &mut tags.prune_empty,
+                |s| Tags::parse_list(s),
+                (Msg::GuiPruneEmptySet, Msg::GuiErrorSetTags),
+            );
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.cfg.tags.fix_refs, msg!(GuiFixRefs))
+                    .on_hover_text(msg!(HelpFixRefs));
+                ui.checkbox(&mut self.cfg.tags.keep_cover, msg!(GuiKeepCover))
+                    .on_hover_text(msg!(HelpKeepCover));
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.cfg.tags.strip_comments, msg!(GuiStripComments))
+                    .on_hover_text(msg!(HelpStripComments));
+                let mut strip_pis = self.cfg.tags.pis == Pis::Strip;
+                if ui
+                    .checkbox(&mut strip_pis, msg!(GuiStripPis))
+                    .on_hover_text(msg!(HelpStripPis))
+                    .changed()
+                {
+                    self.cfg.tags.pis = if strip_pis { Pis::Strip } else { Pis::Keep };
                 }
-            }
+                ui.checkbox(&mut self.cfg.tags.strip_doctype, msg!(GuiStripDoctype))
+                    .on_hover_text(msg!(HelpStripDoctype));
+            });
+            ui.horizontal(|ui| {
+                ui.label(msg!(GuiMaxBinarySize))
+                    .on_hover_text(msg!(HelpMaxBinarySize));
+                if ui
+                    .text_edit_singleline(&mut self.max_binary_size_buf)
+                    .lost_focus()
+                {
+                    self.set_max_binary_size_from_buf();
+                }
+            });
+            ui.add_space(10.0);
+
+            egui::ComboBox::from_label(msg!(GuiOutputStyle))
+                .selected_text(self.cfg.style.as_str())
+                .show_ui(ui, |ui| {
+                    for style in OutputStyle::ALL {
+                        ui.selectable_value(&mut self.cfg.style, style, style.as_str());
+                    }
+                })
+                .response
+                .on_hover_text(msg!(HelpStyle));
+            ui.add_space(10.0);
+
+            egui::ComboBox::from_label(msg!(GuiValidation))
+                .selected_text(self.cfg.validation.as_str())
+                .show_ui(ui, |ui| {
+                    for v in Validation::ALL {
+                        ui.selectable_value(&mut self.cfg.validation, v, v.as_str());
+                    }
+                })
+                .response
+                .on_hover_text(msg!(HelpValidate));
             ui.add_space(10.0);
 
             let input_is_dir = matches!(self.cfg.input, Input::Dir(_));
@@ -147,19 +245,51 @@ impl eframe::App for App {
                     ui.add(egui::DragValue::new(&mut self.cfg.recursive).range(0..=255));
                 });
             });
+            ui.checkbox(&mut self.cfg.sniff, msg!(GuiSniff))
+                .on_hover_text(msg!(HelpSniff));
             ui.add_space(10.0);
 
             ui.horizontal(|ui| {
-                ui.add_enabled_ui(!self.cfg.unzip, |ui| {
+                let is_container = self.cfg.container.is_some();
+                ui.add_enabled_ui(!self.cfg.unzip && !is_container, |ui| {
                     ui.checkbox(&mut self.cfg.zip, "zip")
                         .on_hover_text(msg!(HelpZip));
                 });
-                ui.add_enabled_ui(!self.cfg.zip, |ui| {
+                ui.add_enabled_ui(!self.cfg.zip && !is_container, |ui| {
                     ui.checkbox(&mut self.cfg.unzip, "unzip")
                         .on_hover_text(msg!(HelpUnzip));
                 });
             });
 
+            ui.add_enabled_ui(!self.cfg.zip && !self.cfg.unzip, |ui| {
+                egui::ComboBox::from_label(msg!(GuiContainer))
+                    .selected_text(
+                        self.cfg
+                            .container
+                            .map_or(msg!(GuiSameAsInput), |ty| ty.as_extension()),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.cfg.container, None, msg!(GuiSameAsInput));
+                        for ty in InputFileType::ALL.into_iter().filter(|ty| !ty.is_zip()) {
+                            ui.selectable_value(
+                                &mut self.cfg.container,
+                                Some(ty),
+                                ty.as_extension(),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text(msg!(HelpContainer));
+            });
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.cfg.utf8, "UTF-8")
+                    .on_hover_text(msg!(HelpUtf8));
+                ui.checkbox(&mut self.cfg.lenient, msg!(GuiLenient))
+                    .on_hover_text(msg!(HelpLenient));
+                ui.checkbox(&mut self.cfg.dry_run, msg!(GuiDryRun))
+                    .on_hover_text(msg!(HelpDryRun));
+            });
             ui.checkbox(&mut self.cfg.force, msg!(GuiOverwrite))
                 .on_hover_text(msg!(HelpForce));
 
@@ -186,7 +316,12 @@ impl eframe::App for App {
 
 impl App {
     fn set_input_from_buf(&mut self, buf: impl AsRef<Path>) {
-        match Input::new(buf) {
+        // GUI has no stdin, so `-` is a file in the current directory.
+        let buf = match buf.as_ref() {
+            p if *p == *Path::new(InputFile::STDIN_PATH) => Path::new(".").join(p),
+            p => p.to_path_buf(),
+        };
+        match Input::new_with(buf, self.cfg.sniff) {
             Ok(input) => self.set_input(input),
             Err(e) => error!("{}: '{}'", Msg::GuiErrorSetInput, e),
         }
@@ -207,6 +342,24 @@ impl App {
         }
     }
 
+    fn set_max_binary_size_from_buf(&mut self) {
+        let size = match self.max_binary_size_buf.trim() {
+            "" => None,
+            buf => match Tags::parse_size(buf) {
+                Ok(size) => Some(size),
+                Err(e) => return error!("{}: {}", Msg::GuiErrorSetMaxBinarySize, e),
+            },
+        };
+        if size != self.cfg.tags.max_binary_size {
+            self.cfg.tags.max_binary_size = size;
+            info!(
+                "{}: '{}'",
+                Msg::GuiMaxBinarySizeSet,
+                self.max_binary_size_buf
+            );
+        }
+    }
+
     fn set_output(&mut self, dir: impl Into<Box<Path>>) {
         let new = Output {
             dir: dir.into(),
@@ -221,8 +374,35 @@ impl App {
     }
 }
 
+/// Shows a text field of a comma-separated list, parsing it into the list when
+/// the field loses focus. Logs `msgs.0` if the list is changed, and `msgs.1`
+/// with a parsing error.
+fn list_field<T: fmt::Display + Eq + Hash>(
+    ui: &mut egui::Ui,
+    buf: &mut String,
+    list: &mut IndexSet<T>,
+    parse: impl FnOnce(&str) -> Result<IndexSet<T>>,
+    msgs: (Msg, Msg),
+) {
+    if !ui.text_edit_singleline(buf).lost_focus() {
+        return;
+    }
+    match parse(buf) {
+        Ok(new) => {
+            *buf = DisplayList(&new).to_string();
+            if new != *list {
+                *list = new;
+                info!("{}: '{}'", msgs.0, buf);
+            }
+        }
+        Err(e) => error!("{}: {}", msgs.1, e),
+    }
+}
+
 fn start(cfg: &mut Config) -> Result<()> {
-    cfg.output.create_dirs()?;
+    if !cfg.dry_run {
+        cfg.output.create_dirs()?;
+    }
     let res = cfg.run();
     cfg.output.remove_created_dirs();
     res
@@ -231,8 +411,11 @@ fn start(cfg: &mut Config) -> Result<()> {
 impl Input {
     fn display(&self) -> std::path::Display<'_> {
         let path = match self {
-            Self::Dir(path) => path,
-            Self::File(InputFile { path, .. }) => path,
+            Self::Dir(path) => &**path,
+            Self::File(InputFile { path, .. }) => &**path,
+            Self::Stdin => Path::new(InputFile::STDIN_PATH),
+            // Not set by GUI.
+            Self::Many(_) => Path::new(""),
         };
         path.display()
     }
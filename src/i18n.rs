@@ -35,14 +35,36 @@ pub enum Msg {
     NotFoundAValidLangCode,
     NotFoundAnyBookInDirectory,
     NotFoundAnyFb2InArchive,
+    NotABook,
+    StdoutNeedsOneBook,
+    StdoutIsTakenByBook,
     Overwriting,
     OverwritingBooks,
+    RecompressingImage,
     RemovingInputFile,
     RemovingTempDirectory,
     RemovingTempFile,
     Skipping,
     SuccessCleanedAndSavedTo,
     SuccessOverwritedFrom,
+    MisdeclaredEncoding,
+    Decoding,
+    RepairedXml,
+    KeepingOriginal,
+    WouldRemoveFrom,
+    WouldRemoveTotal,
+    Bytes,
+    ClosedTags,
+    EscapedAmpersands,
+    CompletedReferences,
+    DroppedEndTags,
+    InvalidFb2,
+    AtPosition,
+    MissingTag,
+    UnexpectedTag,
+    MisplacedTag,
+    TooManyTags,
+    UnexpectedText,
     Error,
     Warning,
     Debug,
@@ -58,16 +80,65 @@ pub enum Msg {
     GuiOutputSet,
     GuiErrorSetOutput,
     GuiRemoveTags,
+    GuiKeepOnlyTags,
+    GuiUnwrapTags,
+    GuiUnwrapTagsSet,
+    GuiStripAttrs,
+    GuiStripAttrsSet,
+    GuiErrorSetStripAttrs,
+    GuiPruneEmpty,
+    GuiPruneEmptySet,
+    GuiFixRefs,
+    GuiKeepCover,
+    GuiStripComments,
+    GuiStripPis,
+    GuiStripDoctype,
+    GuiMaxBinarySize,
+    GuiMaxBinarySizeSet,
+    GuiErrorSetMaxBinarySize,
     GuiTagsSet,
+    GuiErrorSetTags,
+    GuiOutputStyle,
     GuiMultithreading,
     GuiRecursiveSearch,
     GuiOverwrite,
+    GuiLenient,
+    GuiDryRun,
+    GuiSniff,
+    GuiContainer,
+    GuiSameAsInput,
+    GuiValidation,
     GuiStopOnError,
     GuiLog,
     HelpInput,
+    HelpFilesFrom,
     HelpOutput,
     HelpRecursive,
     HelpTags,
+    HelpUnwrapTags,
+    HelpStripAttrs,
+    HelpPruneEmpty,
+    HelpStripComments,
+    HelpStripPis,
+    HelpKeepPis,
+    HelpStripDoctype,
+    HelpKeep,
+    HelpLocalNames,
+    HelpFixRefs,
+    HelpKeepCover,
+    HelpMaxBinarySize,
+    HelpMaxWidth,
+    HelpMaxHeight,
+    HelpGrayscale,
+    HelpJpegQuality,
+    HelpStyle,
+    HelpUtf8,
+    HelpLenient,
+    HelpValidate,
+    HelpFormat,
+    HelpContainer,
+    HelpSniff,
+    HelpDryRun,
     HelpZip,
     HelpUnzip,
     HelpForce,
@@ -6,14 +6,36 @@ impl_msg_as_str!(
     NotFoundAValidLangCode => "Not found a valid language code",
     NotFoundAnyBookInDirectory => "Not found any book in directory",
     NotFoundAnyFb2InArchive => "Not found any fb2 in archive",
+    NotABook => "Is not a FictionBook",
+    StdoutNeedsOneBook => "Writing to stdout needs one input book",
+    StdoutIsTakenByBook => "Writing the book to stdout conflicts with --format json|ndjson",
     Overwriting => "Overwriting",
     OverwritingBooks => "Overwriting books",
+    RecompressingImage => "Recompressing image",
     RemovingInputFile => "Removing input file",
     RemovingTempDirectory => "Removing temp directory",
     RemovingTempFile => "Removing temp file",
     Skipping => "Skipping",
     SuccessCleanedAndSavedTo => "Success cleaned and saved to",
     SuccessOverwritedFrom => "Success overwrited from",
+    MisdeclaredEncoding => "Mis-declared encoding",
+    Decoding => "decoding as",
+    RepairedXml => "Repaired XML",
+    KeepingOriginal => "Keeping the original",
+    WouldRemoveFrom => "Would remove from",
+    WouldRemoveTotal => "Would remove in total",
+    Bytes => "bytes",
+    ClosedTags => "closed tags",
+    EscapedAmpersands => "escaped ampersands",
+    CompletedReferences => "completed references",
+    DroppedEndTags => "dropped end tags",
+    InvalidFb2 => "Invalid FB2",
+    AtPosition => "at position",
+    MissingTag => "missing tag",
+    UnexpectedTag => "unexpected tag",
+    MisplacedTag => "misplaced tag",
+    TooManyTags => "too many tags",
+    UnexpectedText => "unexpected text",
     Error => "Error",
     Warning => "Warning",
     Debug => "Debug",
@@ -29,17 +51,66 @@ impl_msg_as_str!(
     GuiOutputSet => "Output set",
     GuiErrorSetOutput => "set output",
     GuiRemoveTags => "Remove tags:",
+    GuiKeepOnlyTags => "keep only",
+    GuiUnwrapTags => "Unwrap tags:",
+    GuiUnwrapTagsSet => "Unwrap tags set",
+    GuiStripAttrs => "Strip attributes:",
+    GuiStripAttrsSet => "Strip attributes set",
+    GuiErrorSetStripAttrs => "set strip attributes",
+    GuiPruneEmpty => "Remove empty tags:",
+    GuiPruneEmptySet => "Remove empty tags set",
+    GuiFixRefs => "fix image references",
+    GuiKeepCover => "keep cover",
+    GuiStripComments => "strip comments",
+    GuiStripPis => "PIs",
+    GuiStripDoctype => "DOCTYPE",
+    GuiMaxBinarySize => "Max binary size:",
+    GuiMaxBinarySizeSet => "Max binary size set",
+    GuiErrorSetMaxBinarySize => "set max binary size",
     GuiTagsSet => "Tags set",
+    GuiErrorSetTags => "set tags",
+    GuiOutputStyle => "Output style",
     GuiMultithreading => "Multithreading:",
     GuiRecursiveSearch => "Recursive search:",
     GuiOverwrite => "overwrite",
+    GuiLenient => "repair XML",
+    GuiDryRun => "dry run",
+    GuiSniff => "detect by content",
+    GuiContainer => "container",
+    GuiSameAsInput => "same as input",
+    GuiValidation => "Validation",
     GuiStopOnError => "stop on error",
     GuiLog => "Log:",
-    HelpInput => "Input directory OR file",
-    HelpOutput => "Save output books to directory",
+    HelpInput => "Input directory OR file OR glob OR - for stdin (repeatable)",
+    HelpFilesFrom => "Read input paths from file, separated by NUL or newlines",
+    HelpOutput => "Save output books to directory OR - for stdout",
     HelpJobs => "Max parallel jobs (multithreading)",
     HelpRecursive => "Recursive book search [up to n]",
     HelpTags => "Remove tags from book structure",
+    HelpUnwrapTags => "Unwrap tags, keeping their content",
+    HelpStripAttrs => "Strip attributes [of tags n] from kept tags",
+    HelpPruneEmpty => "Remove tags left empty [default: p,section]",
+    HelpStripComments => "Strip comments",
+    HelpStripPis => "Strip processing instructions",
+    HelpKeepPis => "Keep only processing instructions matching regex",
+    HelpStripDoctype => "Strip DOCTYPE",
+    HelpKeep => "Keep only --tags, removing others",
+    HelpLocalNames => "Match tags by local names, ignoring namespaces",
+    HelpFixRefs => "Remove broken images and unused binaries",
+    HelpKeepCover => "Keep the cover image, removing other images",
+    HelpMaxBinarySize => "Remove binaries larger than size (e.g. 200K) and their images",
+    HelpMaxWidth => "Downscale images to max width",
+    HelpMaxHeight => "Downscale images to max height",
+    HelpGrayscale => "Convert images to grayscale",
+    HelpJpegQuality => "Recompress images as JPEG with quality [default: 85]",
+    HelpStyle => "Output XML style [default: preserve]",
+    HelpUtf8 => "Re-encode output books to UTF-8",
+    HelpLenient => "Repair malformed XML instead of skipping books",
+    HelpValidate => "Validate output books against FB2 schema: warn or fail",
+    HelpFormat => "Print per-book results as JSON or NDJSON, logging to stderr",
+    HelpContainer => "Save output books as fb2, fb2.zip, fbz, fb2.gz, fb2.bz2 or fb2.xz",
+    HelpSniff => "Detect books by content instead of extension",
+    HelpDryRun => "Report what would be removed, without writing anything",
     HelpZip => "Save output books as fb2.zip",
     HelpUnzip => "Save output books as fb2",
     HelpForce => "Overwrite input books",
@@ -65,7 +65,7 @@ fn get_lang_from_system_locale() -> Option<Lang> {
         .ok()
         .or_else(|| env::var("LANG").ok())
         .or_else(|| env::var("LC_MESSAGES").ok())
-        .or_else(|| get_system_locale_fallback())?;
+        .or_else(get_system_locale_fallback)?;
 
     return get_lang_from_str(&locale);
 
@@ -6,14 +6,36 @@ impl_msg_as_str!(
     NotFoundAValidLangCode => "Не найден валидный код языка",
     NotFoundAnyBookInDirectory => "Книги в директории не найдены",
     NotFoundAnyFb2InArchive => "Fb2 не найден в архиве",
+    NotABook => "Не является FictionBook",
+    StdoutNeedsOneBook => "Для вывода в stdout нужна одна входящая книга",
+    StdoutIsTakenByBook => "Вывод книги в stdout несовместим с --format json|ndjson",
     Overwriting => "Перезапись",
     OverwritingBooks => "Перезапись книг",
+    RecompressingImage => "Пережатие изображения",
     RemovingInputFile => "Удаление входящего файла",
     RemovingTempDirectory => "Удаление временной директории",
     RemovingTempFile => "Удаление временного файла",
     Skipping => "Пропуск",
     SuccessCleanedAndSavedTo => "Успешно очищено и сохранено в",
     SuccessOverwritedFrom => "Успешно перезаписано из",
+    MisdeclaredEncoding => "Неверно объявлена кодировка",
+    Decoding => "декодирование как",
+    RepairedXml => "Исправлен XML",
+    KeepingOriginal => "Оставляю исходный",
+    WouldRemoveFrom => "Будет удалено из",
+    WouldRemoveTotal => "Всего будет удалено",
+    Bytes => "байт",
+    ClosedTags => "закрыто тегов",
+    EscapedAmpersands => "экранировано амперсандов",
+    CompletedReferences => "дополнено ссылок",
+    DroppedEndTags => "удалено закрывающих тегов",
+    InvalidFb2 => "Невалидный FB2",
+    AtPosition => "в позиции",
+    MissingTag => "отсутствует тег",
+    UnexpectedTag => "неожиданный тег",
+    MisplacedTag => "тег не на своём месте",
+    TooManyTags => "слишком много тегов",
+    UnexpectedText => "неожиданный текст",
     Error => "Ошибка",
     Warning => "Предупреждение",
     Debug => "Отладка",
@@ -29,17 +51,66 @@ impl_msg_as_str!(
     GuiOutputSet => "Директория сохранения установлена",
     GuiErrorSetOutput => "установка директории сохранения",
     GuiRemoveTags => "Удалить теги:",
+    GuiKeepOnlyTags => "оставить только",
+    GuiUnwrapTags => "Развернуть теги:",
+    GuiUnwrapTagsSet => "Разворачиваемые теги установлены",
+    GuiStripAttrs => "Удалить атрибуты:",
+    GuiStripAttrsSet => "Удаляемые атрибуты установлены",
+    GuiErrorSetStripAttrs => "установка удаляемых атрибутов",
+    GuiPruneEmpty => "Удалить пустые теги:",
+    GuiPruneEmptySet => "Удаляемые пустые теги установлены",
+    GuiFixRefs => "исправить ссылки на изображения",
+    GuiKeepCover => "оставить обложку",
+    GuiStripComments => "удалить комментарии",
+    GuiStripPis => "PI",
+    GuiStripDoctype => "DOCTYPE",
+    GuiMaxBinarySize => "Макс. размер бинарных данных:",
+    GuiMaxBinarySizeSet => "Установлен макс. размер бинарных данных",
+    GuiErrorSetMaxBinarySize => "установка макс. размера бинарных данных",
     GuiTagsSet => "Теги установлены",
+    GuiErrorSetTags => "установка тегов",
+    GuiOutputStyle => "Стиль вывода",
     GuiMultithreading => "Многопоточность:",
     GuiRecursiveSearch => "Рекурсивный поиск:",
     GuiOverwrite => "перезаписать",
+    GuiLenient => "исправлять XML",
+    GuiDryRun => "пробный запуск",
+    GuiSniff => "по содержимому",
+    GuiContainer => "контейнер",
+    GuiSameAsInput => "как у входной",
+    GuiValidation => "Валидация",
     GuiStopOnError => "остановить при ошибке",
     GuiLog => "Лог:",
-    HelpInput => "Входящая директория ИЛИ файл",
-    HelpOutput => "Сохранить книги в директорию",
+    HelpInput => "Входящая директория ИЛИ файл ИЛИ шаблон ИЛИ - для stdin (можно повторять)",
+    HelpFilesFrom => "Прочитать входящие пути из файла, разделенные NUL или переводами строк",
+    HelpOutput => "Сохранить книги в директорию ИЛИ - для stdout",
     HelpJobs => "Максимум параллельных очисток (многопоточность)",
     HelpRecursive => "Рекурсивный поиск книг [до n]",
     HelpTags => "Удалить теги из структуры книг",
+    HelpUnwrapTags => "Развернуть теги, сохранив их содержимое",
+    HelpStripAttrs => "Удалить атрибуты [тегов n] из оставленных тегов",
+    HelpPruneEmpty => "Удалить оставшиеся пустыми теги [по умолчанию: p,section]",
+    HelpStripComments => "Удалить комментарии",
+    HelpStripPis => "Удалить инструкции обработки",
+    HelpKeepPis => "Оставить только инструкции обработки, подходящие под regex",
+    HelpStripDoctype => "Удалить DOCTYPE",
+    HelpKeep => "Оставить только --tags, удалив остальные",
+    HelpLocalNames => "Сравнивать теги по локальным именам, игнорируя пространства имен",
+    HelpFixRefs => "Удалить битые изображения и неиспользуемые бинарные данные",
+    HelpKeepCover => "Оставить обложку, удалив остальные изображения",
+    HelpMaxBinarySize => "Удалить бинарные данные больше размера (напр. 200K) и их изображения",
+    HelpMaxWidth => "Уменьшить изображения до макс. ширины",
+    HelpMaxHeight => "Уменьшить изображения до макс. высоты",
+    HelpGrayscale => "Преобразовать изображения в оттенки серого",
+    HelpJpegQuality => "Пережать изображения в JPEG с качеством [по умолчанию: 85]",
+    HelpStyle => "Стиль выходного XML [по умолчанию: preserve]",
+    HelpUtf8 => "Перекодировать выходные книги в UTF-8",
+    HelpLenient => "Исправлять некорректный XML вместо пропуска книг",
+    HelpValidate => "Проверять выходные книги по схеме FB2: warn или fail",
+    HelpFormat => "Выводить результаты по книгам в JSON или NDJSON, логируя в stderr",
+    HelpContainer => "Сохранять выходные книги как fb2, fb2.zip, fbz, fb2.gz, fb2.bz2 или fb2.xz",
+    HelpSniff => "Определять книги по содержимому, а не по расширению",
+    HelpDryRun => "Показать, что будет удалено, ничего не записывая",
     HelpZip => "Сохранить книги в формате fb2.zip",
     HelpUnzip => "Сохранить книги в формате fb2",
     HelpForce => "Перезаписать входящие книги",
@@ -1,24 +1,44 @@
 mod config;
+mod error;
+mod format_xml;
 mod i18n;
+#[cfg(feature = "images")]
+mod recompress_images;
 mod remove_xml_tags;
+mod repair_xml;
+mod validate_fb2;
+mod xml_to_utf8;
 
 #[cfg(feature = "cli")]
 pub mod cli;
 #[cfg(feature = "gui")]
 pub mod gui;
 
-use std::{error::Error, result};
+use std::result;
 
-pub type Result<T> = result::Result<T, Box<dyn Error>>;
+pub type Result<T> = result::Result<T, Error>;
 
+#[cfg(feature = "images")]
+pub use config::images::Images;
 pub use config::{
     Config,
-    input::{Input, InputFile, InputFileType},
-    output::Output,
-    tags::Tags,
+    input::{Input, InputFile, InputFileType, InputSource},
+    output::{Output, OutputStyle},
+    report::{BookReport, BookStatus, EntryReport, Report, ReportFormat},
+    tags::{AttrSelector, Pis, Selector, Step, StripAttr, Tags},
+    validation::Validation,
 };
+pub use error::Error;
+pub use format_xml::format_xml;
 pub use i18n::{Lang, Msg};
-pub use remove_xml_tags::remove_xml_tags;
+#[cfg(feature = "images")]
+pub use recompress_images::recompress_xml_images;
+pub use remove_xml_tags::{
+    Refs, Removed, remove_xml_tags, remove_xml_tags_with_refs, scan_xml_refs,
+};
+pub use repair_xml::{Repairs, repair_xml};
+pub use validate_fb2::{Violation, ViolationKind, validate_fb2};
+pub use xml_to_utf8::{detect_xml_encoding, xml_to_utf8};
 
 fn log_prefix_root(level: log::Level) -> &'static str {
     use log::Level;
@@ -1,11 +1,57 @@
 #[cfg(feature = "cli")]
-fn main() -> fb2_clean::Result<()> {
+fn main() -> std::process::ExitCode {
+    match run() {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+fn run() -> fb2_clean::Result<()> {
     use clap::Parser;
+    use fb2_clean::{BookReport, ReportFormat, cli};
+    use std::io;
+
     let mut cfg = fb2_clean::Config::parse();
-    cfg.output.create_dirs()?;
+    if !cfg.dry_run && !cfg.stdout {
+        cfg.output.create_dirs()?;
+    }
 
-    fb2_clean::cli::CliLogger::init();
-    let res = cfg.run();
+    let res = if cfg.stdout {
+        cli::CliLogger::init_stderr();
+        cfg.run()
+    } else if cfg.format == ReportFormat::Text {
+        cli::CliLogger::init();
+        cfg.run()
+    } else {
+        cli::CliLogger::init_stderr();
+        let is_ndjson = cfg.format == ReportFormat::Ndjson;
+        let on_book = |b: &BookReport| {
+            if is_ndjson {
+                let _ = cli::write_book_event(&mut io::stdout().lock(), b);
+            }
+        };
+        match cfg.run_report_with(on_book) {
+            Ok(report) => {
+                let mut stdout = io::stdout().lock();
+                let res = match is_ndjson {
+                    true => cli::write_summary_events(&mut stdout, &report),
+                    false => cli::write_report(&mut stdout, &report, cfg.format),
+                };
+                match report.error {
+                    Some(e) => Err(e),
+                    None => res.map_err(Into::into),
+                }
+            }
+            Err(e) => {
+                let _ = cli::write_error(&mut io::stdout().lock(), &e, cfg.format);
+                Err(e)
+            }
+        }
+    };
     cfg.output.remove_created_dirs();
     res
 }
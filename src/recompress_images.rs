@@ -0,0 +1,169 @@
+use crate::{Error, Images, Msg, Result, config::tags::Element};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use image::{DynamicImage, Rgb, RgbImage, Rgba, codecs::jpeg::JpegEncoder, imageops::FilterType};
+use log::warn;
+use quick_xml::{
+    NsReader, Writer,
+    events::{BytesEnd, BytesStart, BytesText, Event},
+};
+use std::{
+    error::Error as StdError,
+    io::{BufRead, Write},
+};
+
+/// Recompresses images of `binary` tags in an XML as JPEG, according to [`Images`].
+///
+/// Updates `content-type` of recompressed `binary` tags. Keeps a `binary` tag
+/// unchanged if it is not a decodable image or its JPEG is not smaller.
+/// Matches `binary` tags by local names if `local_names` is `true`.
+pub fn recompress_xml_images<R, W>(
+    src: &mut NsReader<R>,
+    dest: &mut Writer<W>,
+    images: &Images,
+    local_names: bool,
+) -> Result<()>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut buf = Vec::<u8>::new();
+    // Open `binary` tag with its base64 payload.
+    let mut binary: Option<(BytesStart<'static>, Vec<u8>)> = None;
+
+    loop {
+        match src.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if binary.is_none() && is_binary(&e, src, local_names) => {
+                binary = Some((e.into_owned(), Vec::new()));
+            }
+            Ok(Event::Text(e)) if binary.is_some() => {
+                if let Some((_, payload)) = &mut binary {
+                    payload.extend_from_slice(&e);
+                }
+            }
+            Ok(Event::End(e)) if binary.is_some() => {
+                if let Some((start, payload)) = binary.take() {
+                    write_binary(dest, start, payload, e, images)?;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(event) => {
+                // Unexpected content of `binary`: keeps it as is.
+                if let Some((start, payload)) = binary.take() {
+                    dest.write_event(Event::Start(start))?;
+                    dest.write_event(Event::Text(BytesText::from_escaped(
+                        String::from_utf8_lossy(&payload),
+                    )))?;
+                }
+                dest.write_event(event)?;
+            }
+            Err(err) => return Err(Error::xml(err, src.error_position())),
+        }
+        buf.clear();
+    }
+
+    Ok(())
+}
+
+fn is_binary<R>(e: &BytesStart, src: &NsReader<R>, local_names: bool) -> bool {
+    Element::new(e.borrow(), src.resolver()).is_fb2(b"binary", local_names)
+}
+
+fn write_binary<W: Write>(
+    dest: &mut Writer<W>,
+    start: BytesStart<'static>,
+    payload: Vec<u8>,
+    end: BytesEnd,
+    images: &Images,
+) -> Result<()> {
+    let (start, payload) = match recompress(&payload, images) {
+        Ok(Some(jpeg)) => (with_content_type(&start, b"image/jpeg"), jpeg),
+        Ok(None) => (start, payload),
+        Err(e) => {
+            let id = start
+                .try_get_attribute("id")
+                .ok()
+                .flatten()
+                .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+                .unwrap_or_default();
+            warn!(
+                "{} '{}': {}. {}",
+                Msg::RecompressingImage,
+                id,
+                e,
+                Msg::Skipping
+            );
+            (start, payload)
+        }
+    };
+
+    dest.write_event(Event::Start(start))?;
+    dest.write_event(Event::Text(BytesText::from_escaped(
+        String::from_utf8_lossy(&payload),
+    )))?;
+    dest.write_event(Event::End(end))?;
+    Ok(())
+}
+
+/// Decodes a base64 image, applies [`Images`] and returns it as base64 JPEG,
+/// or `None` if the JPEG is not smaller than the image.
+///
+/// Errors are only logged, so they are not converted to [`Error`].
+fn recompress(
+    payload: &[u8],
+    images: &Images,
+) -> std::result::Result<Option<Vec<u8>>, Box<dyn StdError>> {
+    let base64: Vec<u8> = payload
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    let bytes = STANDARD.decode(base64)?;
+    let mut img = image::load_from_memory(&bytes)?;
+
+    let max_width = images.max_width.unwrap_or(img.width());
+    let max_height = images.max_height.unwrap_or(img.height());
+    if img.width() > max_width || img.height() > max_height {
+        img = img.resize(max_width, max_height, FilterType::Lanczos3);
+    }
+
+    // JPEG has no alpha channel.
+    let img = DynamicImage::ImageRgb8(on_white(&img));
+    let img = match images.grayscale {
+        true => DynamicImage::ImageLuma8(img.to_luma8()),
+        false => img,
+    };
+
+    let mut jpeg = Vec::<u8>::new();
+    img.write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, images.quality()))?;
+    Ok((jpeg.len() < bytes.len()).then(|| STANDARD.encode(jpeg).into_bytes()))
+}
+
+/// Blends an image onto a white background.
+fn on_white(img: &DynamicImage) -> RgbImage {
+    let rgba = img.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *rgba.get_pixel(x, y);
+        let blend = |c: u8| ((c as u16 * a as u16 + 255 * (255 - a as u16)) / 255) as u8;
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+fn with_content_type(start: &BytesStart, content_type: &[u8]) -> BytesStart<'static> {
+    let mut new = start.to_owned();
+    new.clear_attributes();
+    let mut is_set = false;
+
+    for a in start.attributes().with_checks(false).flatten() {
+        if a.key.as_ref() == b"content-type" {
+            new.push_attribute((&b"content-type"[..], content_type));
+            is_set = true;
+        } else {
+            new.push_attribute(a);
+        }
+    }
+    if !is_set {
+        new.push_attribute((&b"content-type"[..], content_type));
+    }
+
+    new.into_owned()
+}
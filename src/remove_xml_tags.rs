@@ -1,50 +1,234 @@
-use crate::{Result, Tags};
-use quick_xml::{Reader, Writer, events::Event};
-use std::io::{BufRead, Write};
+mod prune;
+mod refs;
 
-/// Removes specified tags from an XML.
+use crate::{
+    Error, Result, Tags,
+    config::tags::{Element, Scope, TagAction},
+};
+use indexmap::IndexMap;
+use quick_xml::{NsReader, Writer, events::Event};
+use std::{
+    fmt,
+    io::{BufRead, Write},
+};
+
+use prune::Pending;
+use refs::Payload;
+
+pub use refs::{Refs, scan_xml_refs};
+
+/// Counts of tags removed from an XML.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Removed {
+    /// Counts by local names, including tags nested in removed ones.
+    pub tags: IndexMap<String, usize>,
+}
+
+impl Removed {
+    /// Returns the total count of removed tags.
+    pub fn total(&self) -> usize {
+        self.tags.values().sum()
+    }
+
+    /// Adds counts of other removed tags.
+    pub fn extend(&mut self, other: &Removed) {
+        for (name, n) in &other.tags {
+            *self.tags.entry(name.clone()).or_default() += n;
+        }
+    }
+
+    fn add(&mut self, local_name: &[u8]) {
+        let name = String::from_utf8_lossy(local_name);
+        match self.tags.get_mut(&*name) {
+            Some(n) => *n += 1,
+            None => {
+                self.tags.insert(name.into_owned(), 1);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Removed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, n)) in self.tags.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", name, n)?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes specified tags from an XML, returning counts of [`Removed`] tags.
+///
+/// If [`Tags::keep`] is set, removes all tags except the root and the listed ones.
+/// Tags from [`Tags::unwrap`] are replaced by their content. Tags from
+/// [`Tags::prune_empty`] are removed if they are empty after the cleaning.
 pub fn remove_xml_tags<R, W>(
-    src: &mut Reader<R>,
+    src: &mut NsReader<R>,
     dest: &mut Writer<W>,
-    rm_tags: &Tags,
-) -> Result<()>
+    tags: &Tags,
+) -> Result<Removed>
+where
+    R: BufRead,
+    W: Write,
+{
+    walk(src, dest, tags, None).map(|(_, removed)| removed)
+}
+
+/// Removes specified tags from an XML as [`remove_xml_tags`], also removing tags
+/// broken by the cleaning, according to [`Refs`] scanned by [`scan_xml_refs`].
+pub fn remove_xml_tags_with_refs<R, W>(
+    src: &mut NsReader<R>,
+    dest: &mut Writer<W>,
+    tags: &Tags,
+    refs: &Refs,
+) -> Result<Removed>
+where
+    R: BufRead,
+    W: Write,
+{
+    walk(src, dest, tags, Some(refs)).map(|(_, removed)| removed)
+}
+
+/// Writes kept events of `src` to `dest`, returning [`Refs`] of kept tags and
+/// counts of [`Removed`] tags.
+fn walk<R, W>(
+    src: &mut NsReader<R>,
+    dest: &mut Writer<W>,
+    tags: &Tags,
+    refs: Option<&Refs>,
+) -> Result<(Refs, Removed)>
 where
     R: BufRead,
     W: Write,
 {
     let mut buf = Vec::<u8>::new();
+    // Open not removed tags, from the root.
+    let mut ancestors: Vec<Element<'static>> = Vec::new();
+    // Is unwrapped flags of the ancestors.
+    let mut unwrapped: Vec<bool> = Vec::new();
     let mut rm_depth = 0usize;
+    let mut kept = Refs::default();
+    // Payload of an open kept `binary` tag, if sizes are checked.
+    let mut payload: Option<Payload> = None;
+    let mut pending = Pending::default();
+    let mut removed = Removed::default();
 
     loop {
         match src.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if rm_depth > 0 => {
+                removed.add(e.local_name().into_inner());
+                rm_depth += 1;
+            }
             Ok(Event::Start(e)) => {
-                let tag = e.name().into_inner();
-                if rm_depth > 0 || rm_tags.contains(tag) {
-                    rm_depth += 1;
-                } else {
-                    dest.write_event(Event::Start(e))?;
+                let e = Element::new(e, src.resolver());
+                let scope = tags.scope(src.resolver());
+                match action(tags, refs, &kept, &e, &ancestors, &scope) {
+                    TagAction::Keep => {
+                        kept.add(&e, &ancestors, &scope);
+                        if tags.max_binary_size.is_some() {
+                            payload = Payload::new(&e, ancestors.len(), &scope);
+                        }
+                        let start = tags.strip(&e, &ancestors, &scope);
+                        if tags.is_prunable(&e, &ancestors, &scope) {
+                            pending.start(start, ancestors.len());
+                        } else {
+                            pending.write(dest, Event::Start(start))?;
+                        }
+                        ancestors.push(e.into_owned());
+                        unwrapped.push(false);
+                    }
+                    TagAction::Remove => {
+                        removed.add(e.start.local_name().into_inner());
+                        rm_depth += 1;
+                    }
+                    TagAction::Unwrap => {
+                        ancestors.push(e.into_owned());
+                        unwrapped.push(true);
+                    }
                 }
             }
             Ok(Event::End(e)) => {
                 if rm_depth > 0 {
                     rm_depth -= 1;
                 } else {
-                    dest.write_event(Event::End(e))?;
+                    let open = ancestors.pop();
+                    if !unwrapped.pop().unwrap_or(false) {
+                        if !pending.prune(ancestors.len()) {
+                            pending.write(dest, Event::End(e))?;
+                        } else if let Some(open) = open {
+                            removed.add(open.start.local_name().into_inner());
+                        }
+                    }
+                    if payload.as_ref().is_some_and(|p| p.depth == ancestors.len()) {
+                        if let (Some(p), Some(max)) = (payload.take(), tags.max_binary_size) {
+                            kept.add_payload(p, max);
+                        }
+                    }
                 }
             }
-            Ok(Event::Empty(e)) => {
-                let tag = e.name().into_inner();
-                if rm_depth == 0 && !rm_tags.contains(tag) {
-                    dest.write_event(Event::Empty(e))?;
+            Ok(Event::Empty(e)) if rm_depth == 0 => {
+                let e = Element::new(e, src.resolver());
+                let scope = tags.scope(src.resolver());
+                if action(tags, refs, &kept, &e, &ancestors, &scope) == TagAction::Keep
+                    && !tags.is_prunable(&e, &ancestors, &scope)
+                {
+                    kept.add(&e, &ancestors, &scope);
+                    pending.write(dest, Event::Empty(tags.strip(&e, &ancestors, &scope)))?;
+                } else {
+                    removed.add(e.start.local_name().into_inner());
+                }
+            }
+            Ok(Event::Empty(e)) => removed.add(e.local_name().into_inner()),
+            Ok(Event::Text(e)) if rm_depth == 0 => {
+                if let Some(p) = &mut payload {
+                    p.push(&e);
                 }
+                if !pending.whitespace(&e) {
+                    pending.write(dest, Event::Text(e))?;
+                }
+            }
+            Ok(Event::Comment(_)) if tags.strip_comments => (),
+            Ok(Event::DocType(_)) if tags.strip_doctype => (),
+            Ok(Event::PI(e)) if !tags.pis.keeps(&e) => (),
+            Ok(Event::Eof) => {
+                pending.flush(dest)?;
+                break;
             }
-            Ok(Event::Eof) => break,
-            Ok(event) if rm_depth == 0 => dest.write_event(event)?,
+            Ok(event) if rm_depth == 0 => pending.write(dest, event)?,
             Ok(_) => (),
-            Err(err) => return Err(Box::new(err)),
+            Err(err) => return Err(Error::xml(err, src.error_position())),
         }
         buf.clear();
     }
 
-    Ok(())
+    Ok((kept, removed))
+}
+
+/// Returns an action for a tag. `kept` are refs of tags kept so far, used to
+/// find the cover `binary` while scanning refs, so its size is known.
+fn action(
+    tags: &Tags,
+    refs: Option<&Refs>,
+    kept: &Refs,
+    e: &Element,
+    ancestors: &[Element],
+    scope: &Scope,
+) -> TagAction {
+    // A large binary is removed even if it is the cover.
+    if tags.max_binary_size.is_some() && refs.is_some_and(|r| r.is_large(e, scope)) {
+        return TagAction::Remove;
+    }
+    if tags.keep_cover {
+        if let Some(a) = refs::cover_action(refs.unwrap_or(kept), e, ancestors, scope) {
+            return a;
+        }
+    }
+    match tags.action(e, ancestors, scope) {
+        TagAction::Remove => TagAction::Remove,
+        _ if tags.fix_refs && refs.is_some_and(|r| r.is_broken(e, scope)) => TagAction::Remove,
+        a => a,
+    }
 }
@@ -0,0 +1,62 @@
+use crate::Result;
+use quick_xml::{
+    Writer,
+    events::{BytesStart, BytesText, Event},
+};
+use std::io::Write;
+
+/// Buffered events from a start of a prunable tag, written only if the tag
+/// gets a content: a non-whitespace text, a not prunable tag, or a comment or
+/// a processing instruction kept by the strip rules, which are applied first.
+#[derive(Debug, Default)]
+pub(super) struct Pending {
+    events: Vec<Event<'static>>,
+    /// Depths and event indices of buffered starts.
+    starts: Vec<(usize, usize)>,
+}
+
+impl Pending {
+    /// Buffers a start of a prunable tag at the depth.
+    pub fn start(&mut self, start: BytesStart, depth: usize) {
+        self.starts.push((depth, self.events.len()));
+        self.events.push(Event::Start(start.into_owned()));
+    }
+
+    /// Buffers a whitespace text, returning `false` if it is not buffered.
+    pub fn whitespace(&mut self, text: &BytesText) -> bool {
+        if self.starts.is_empty() || !text.iter().all(u8::is_ascii_whitespace) {
+            return false;
+        }
+        self.events.push(Event::Text(text.clone().into_owned()));
+        true
+    }
+
+    /// Drops a buffered tag ending at the depth, returning `false` if the tag
+    /// is not buffered.
+    pub fn prune(&mut self, depth: usize) -> bool {
+        match self.starts.last() {
+            Some(&(d, i)) if d == depth => {
+                self.starts.pop();
+                self.events.truncate(i);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Writes buffered events and then the event.
+    pub fn write<W: Write>(&mut self, dest: &mut Writer<W>, event: Event) -> Result<()> {
+        self.flush(dest)?;
+        dest.write_event(event)?;
+        Ok(())
+    }
+
+    /// Writes buffered events.
+    pub fn flush<W: Write>(&mut self, dest: &mut Writer<W>) -> Result<()> {
+        self.starts.clear();
+        for event in self.events.drain(..) {
+            dest.write_event(event)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{
+    Result, Tags,
+    config::tags::{Element, Scope, TagAction},
+};
+use quick_xml::{NsReader, Writer};
+use std::{
+    collections::HashSet,
+    io::{self, BufRead},
+};
+
+/// References between `image` and `binary` tags kept in an XML.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Refs {
+    binaries: HashSet<Box<[u8]>>,
+    images: HashSet<Box<[u8]>>,
+    covers: HashSet<Box<[u8]>>,
+    large: HashSet<Box<[u8]>>,
+}
+
+/// A payload of an open `binary` tag.
+#[derive(Debug)]
+pub(super) struct Payload {
+    /// Depth of the `binary` tag.
+    pub depth: usize,
+    id: Box<[u8]>,
+    chars: u64,
+    padding: u64,
+}
+
+/// Scans [`Refs`] of an XML, cleaning it by [`Tags`] without output.
+pub fn scan_xml_refs<R: BufRead>(src: &mut NsReader<R>, tags: &Tags) -> Result<Refs> {
+    super::walk(src, &mut Writer::new(io::sink()), tags, None).map(|(refs, _)| refs)
+}
+
+impl Refs {
+    /// Returns IDs of kept `binary` tags.
+    pub fn binaries(&self) -> impl Iterator<Item = &[u8]> {
+        self.binaries.iter().map(|id| &**id)
+    }
+
+    /// Returns local targets (`#id`) of kept `image` tags, without `#`.
+    pub fn images(&self) -> impl Iterator<Item = &[u8]> {
+        self.images.iter().map(|id| &**id)
+    }
+
+    /// Returns local targets (`#id`) of kept `image` tags in `coverpage`,
+    /// without `#`.
+    pub fn covers(&self) -> impl Iterator<Item = &[u8]> {
+        self.covers.iter().map(|id| &**id)
+    }
+
+    /// Returns IDs of kept `binary` tags with a decoded payload larger than
+    /// [`Tags::max_binary_size`].
+    pub fn large(&self) -> impl Iterator<Item = &[u8]> {
+        self.large.iter().map(|id| &**id)
+    }
+
+    /// Adds references of a kept tag.
+    pub(super) fn add(&mut self, e: &Element, ancestors: &[Element], scope: &Scope) {
+        if e.is_fb2(b"image", scope.local_names) {
+            if let Some(id) = local_target(e, scope) {
+                if is_in_coverpage(ancestors, scope) {
+                    self.covers.insert(id.clone());
+                }
+                self.images.insert(id);
+            }
+        } else if e.is_fb2(b"binary", scope.local_names) {
+            if let Some(id) = e.id() {
+                self.binaries.insert(id.into());
+            }
+        }
+    }
+
+    /// Adds a payload of a kept `binary` tag, if it is larger than `max_size`.
+    pub(super) fn add_payload(&mut self, payload: Payload, max_size: u64) {
+        if payload.decoded_len() > max_size {
+            self.large.insert(payload.id);
+        }
+    }
+
+    /// Returns `true` if a tag is a large `binary` OR an `image` referencing it.
+    pub(super) fn is_large(&self, e: &Element, scope: &Scope) -> bool {
+        if e.is_fb2(b"image", scope.local_names) {
+            local_target(e, scope).is_some_and(|id| self.large.contains(&*id))
+        } else if e.is_fb2(b"binary", scope.local_names) {
+            e.id().is_some_and(|id| self.large.contains(&*id))
+        } else {
+            false
+        }
+    }
+
+    /// Returns `true` if a tag is an `image` referencing a removed `binary`
+    /// OR a `binary` not referenced by any kept `image`.
+    pub(super) fn is_broken(&self, e: &Element, scope: &Scope) -> bool {
+        if e.is_fb2(b"image", scope.local_names) {
+            local_target(e, scope).is_some_and(|id| !self.binaries.contains(&*id))
+        } else if e.is_fb2(b"binary", scope.local_names) {
+            e.id().is_none_or(|id| !self.images.contains(&*id))
+        } else {
+            false
+        }
+    }
+}
+
+impl Payload {
+    /// Returns a new payload of a `binary` tag at the depth, if it has an id.
+    pub(super) fn new(e: &Element, depth: usize, scope: &Scope) -> Option<Payload> {
+        if !e.is_fb2(b"binary", scope.local_names) {
+            return None;
+        }
+        Some(Payload {
+            depth,
+            id: e.id()?.into(),
+            chars: 0,
+            padding: 0,
+        })
+    }
+
+    /// Counts base64 characters of a payload text.
+    pub(super) fn push(&mut self, text: &[u8]) {
+        for &b in text.iter().filter(|b| !b.is_ascii_whitespace()) {
+            self.chars += 1;
+            if b == b'=' {
+                self.padding += 1;
+            }
+        }
+    }
+
+    fn decoded_len(&self) -> u64 {
+        (self.chars * 3 / 4).saturating_sub(self.padding)
+    }
+}
+
+/// Returns an action for a tag by the keep cover rules, if they are applied.
+///
+/// Keeps `coverpage` tags with their `image` tags and the `binary` tags referenced
+/// by them, removing other `image` and `binary` tags. While scanning, `refs`
+/// are of the tags kept so far, as `coverpage` precedes `binary` tags.
+pub(super) fn cover_action(
+    refs: &Refs,
+    e: &Element,
+    ancestors: &[Element],
+    scope: &Scope,
+) -> Option<TagAction> {
+    let keep_or_remove = |keep: bool| {
+        if keep {
+            TagAction::Keep
+        } else {
+            TagAction::Remove
+        }
+    };
+
+    if e.is_fb2(b"coverpage", scope.local_names) {
+        Some(TagAction::Keep)
+    } else if e.is_fb2(b"image", scope.local_names) {
+        Some(keep_or_remove(is_in_coverpage(ancestors, scope)))
+    } else if e.is_fb2(b"binary", scope.local_names) {
+        Some(keep_or_remove(
+            e.id().is_some_and(|id| refs.covers.contains(&*id)),
+        ))
+    } else {
+        None
+    }
+}
+
+fn is_in_coverpage(ancestors: &[Element], scope: &Scope) -> bool {
+    ancestors
+        .iter()
+        .any(|a| a.is_fb2(b"coverpage", scope.local_names))
+}
+
+fn local_target(e: &Element, scope: &Scope) -> Option<Box<[u8]>> {
+    e.href(scope)?.strip_prefix(b"#").map(Into::into)
+}
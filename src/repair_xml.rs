@@ -0,0 +1,182 @@
+use crate::{Error, Msg, Result};
+use quick_xml::{
+    NsReader, Writer,
+    events::{BytesEnd, BytesRef, Event},
+};
+use std::{
+    fmt,
+    io::{BufRead, Write},
+};
+
+/// Repairs made by [`repair_xml`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Repairs {
+    /// Auto-closed open tags.
+    pub closed_tags: usize,
+    /// Escaped bare ampersands.
+    pub escaped_amps: usize,
+    /// References completed with a missing `;`, such as `&amp`.
+    pub completed_refs: usize,
+    /// Dropped unmatched end tags.
+    pub dropped_ends: usize,
+}
+
+impl Repairs {
+    /// Returns `true` if nothing is repaired.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for Repairs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = [
+            (Msg::ClosedTags, self.closed_tags),
+            (Msg::EscapedAmpersands, self.escaped_amps),
+            (Msg::CompletedReferences, self.completed_refs),
+            (Msg::DroppedEndTags, self.dropped_ends),
+        ];
+        let mut is_first = true;
+        for (msg, n) in counts.into_iter().filter(|(_, n)| *n > 0) {
+            if !is_first {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", msg, n)?;
+            is_first = false;
+        }
+        Ok(())
+    }
+}
+
+/// Writes an XML, repairing its common errors.
+///
+/// Auto-closes tags left open before a mismatched end tag or the end of the XML,
+/// escapes bare `&` and drops end tags without a matching open tag. A character
+/// reference or a predefined entity missing `;`, such as `&amp`, is completed.
+/// Other errors are returned as is.
+pub fn repair_xml<R, W>(src: &mut NsReader<R>, dest: &mut Writer<W>) -> Result<Repairs>
+where
+    R: BufRead,
+    W: Write,
+{
+    let config = src.config_mut();
+    config.allow_dangling_amp = true;
+    config.allow_unmatched_ends = true;
+    config.check_end_names = false;
+
+    let mut buf = Vec::<u8>::new();
+    // Ends of open tags, from the root.
+    let mut open: Vec<BytesEnd<'static>> = Vec::new();
+    let mut repairs = Repairs::default();
+
+    loop {
+        match src.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                open.push(e.to_end().into_owned());
+                dest.write_event(Event::Start(e))?;
+            }
+            Ok(Event::End(e)) => match open.iter().rposition(|end| end.name() == e.name()) {
+                Some(i) => {
+                    repairs.closed_tags += open.len() - i - 1;
+                    close(dest, &mut open, i + 1)?;
+                    open.pop();
+                    dest.write_event(Event::End(e))?;
+                }
+                None => repairs.dropped_ends += 1,
+            },
+            Ok(Event::GeneralRef(e)) if !is_ref(&e) => {
+                repairs.escaped_amps += 1;
+                dest.write_event(Event::GeneralRef(BytesRef::new("amp")))?;
+                // Keeps the rest in the source encoding.
+                let w = dest.get_mut();
+                w.write_all(&e)?;
+                w.write_all(b";")?;
+            }
+            // Text never starts with `&` except a dangling one, as references
+            // are read as separate events.
+            Ok(Event::Text(e)) if e.first() == Some(&b'&') => {
+                let rest = &e[1..];
+                let len = missing_semicolon_ref_len(rest);
+                let w = if len > 0 {
+                    repairs.completed_refs += 1;
+                    let w = dest.get_mut();
+                    w.write_all(b"&")?;
+                    w.write_all(&rest[..len])?;
+                    w.write_all(b";")?;
+                    w
+                } else {
+                    repairs.escaped_amps += 1;
+                    dest.write_event(Event::GeneralRef(BytesRef::new("amp")))?;
+                    dest.get_mut()
+                };
+                // Keeps the rest in the source encoding.
+                w.write_all(&rest[len..])?;
+            }
+            Ok(Event::Eof) => {
+                repairs.closed_tags += open.len();
+                close(dest, &mut open, 0)?;
+                break;
+            }
+            Ok(event) => dest.write_event(event)?,
+            Err(err) => return Err(Error::xml(err, src.error_position())),
+        }
+        buf.clear();
+    }
+
+    Ok(repairs)
+}
+
+/// Checks that a reference is a character reference or an entity name.
+fn is_ref(content: &[u8]) -> bool {
+    match content {
+        [b'#', b'x', hex @ ..] => !hex.is_empty() && hex.iter().all(u8::is_ascii_hexdigit),
+        [b'#', dec @ ..] => !dec.is_empty() && dec.iter().all(u8::is_ascii_digit),
+        [first, rest @ ..] => {
+            (first.is_ascii_alphabetic() || matches!(first, b'_' | b':' | 0x80..))
+                && rest.iter().all(|&b| {
+                    b.is_ascii_alphanumeric() || matches!(b, b'_' | b':' | b'.' | b'-' | 0x80..)
+                })
+        }
+        [] => false,
+    }
+}
+
+/// Returns a length of a character reference or a predefined entity at the
+/// start of a text after a dangling `&`, or 0.
+fn missing_semicolon_ref_len(text: &[u8]) -> usize {
+    let (prefix, digits) = match text {
+        [b'#', b'x', ..] => (
+            2,
+            text[2..]
+                .iter()
+                .take_while(|b| b.is_ascii_hexdigit())
+                .count(),
+        ),
+        [b'#', ..] => (
+            1,
+            text[1..].iter().take_while(|b| b.is_ascii_digit()).count(),
+        ),
+        _ => {
+            let name = text
+                .iter()
+                .take_while(|b| b.is_ascii_alphanumeric())
+                .count();
+            return match &text[..name] {
+                b"amp" | b"lt" | b"gt" | b"quot" | b"apos" => name,
+                _ => 0,
+            };
+        }
+    };
+    if digits > 0 { prefix + digits } else { 0 }
+}
+
+/// Writes end tags of open tags, down to the `depth`.
+fn close<W: Write>(dest: &mut Writer<W>, open: &mut Vec<BytesEnd>, depth: usize) -> Result<()> {
+    while open.len() > depth {
+        if let Some(end) = open.pop() {
+            dest.write_event(Event::End(end))?;
+        }
+    }
+    Ok(())
+}
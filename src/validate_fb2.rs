@@ -0,0 +1,234 @@
+mod schema;
+
+use crate::{Error, Msg, Result, config::tags::Element};
+use quick_xml::{NsReader, events::Event};
+use std::{fmt, io::BufRead};
+
+use schema::Model;
+
+/// A violation of the FictionBook content model.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Violation {
+    /// Path of the tag containing the violation, such as
+    /// `/FictionBook/description/title-info`.
+    pub path: String,
+    pub kind: ViolationKind,
+}
+
+/// A kind of [`Violation`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ViolationKind {
+    /// A required child tag is missing.
+    Missing(String),
+    /// A child tag is not allowed.
+    Unexpected(String),
+    /// A child tag is allowed only before previous ones.
+    Misplaced(String),
+    /// A child tag occurs more times than allowed.
+    TooMany(String),
+    /// Non-whitespace text is not allowed.
+    Text,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (msg, name) = match &self.kind {
+            ViolationKind::Missing(n) => (Msg::MissingTag, n),
+            ViolationKind::Unexpected(n) => (Msg::UnexpectedTag, n),
+            ViolationKind::Misplaced(n) => (Msg::MisplacedTag, n),
+            ViolationKind::TooMany(n) => (Msg::TooManyTags, n),
+            ViolationKind::Text => return write!(f, "{}: {}", self.path, Msg::UnexpectedText),
+        };
+        write!(f, "{}: {} '{}'", self.path, msg, name)
+    }
+}
+
+/// Validates an XML against the FictionBook 2.0/2.1 content model, returning
+/// found violations.
+///
+/// Checks the root, required tags, their order and allowed children. Tags of
+/// other namespaces are skipped with their content.
+pub fn validate_fb2<R: BufRead>(src: &mut NsReader<R>) -> Result<Vec<Violation>> {
+    let mut buf = Vec::<u8>::new();
+    // Open tags, from the root.
+    let mut open: Vec<Open> = Vec::new();
+    let mut skip_depth = 0usize;
+    let mut violations: Vec<Violation> = Vec::new();
+
+    loop {
+        let (e, is_empty) = match src.read_event_into(&mut buf) {
+            Ok(Event::Start(_)) if skip_depth > 0 => {
+                skip_depth += 1;
+                buf.clear();
+                continue;
+            }
+            Ok(Event::End(_)) if skip_depth > 0 => {
+                skip_depth -= 1;
+                buf.clear();
+                continue;
+            }
+            Ok(Event::Start(e)) => (Element::new(e, src.resolver()), false),
+            Ok(Event::Empty(e)) if skip_depth == 0 => (Element::new(e, src.resolver()), true),
+            Ok(Event::End(_)) => {
+                if let Some(o) = open.pop() {
+                    o.check_missing(&mut violations);
+                }
+                buf.clear();
+                continue;
+            }
+            Ok(Event::Text(e)) if skip_depth == 0 && !e.iter().all(u8::is_ascii_whitespace) => {
+                text(&mut open, &mut violations);
+                buf.clear();
+                continue;
+            }
+            Ok(Event::CData(_) | Event::GeneralRef(_)) if skip_depth == 0 => {
+                text(&mut open, &mut violations);
+                buf.clear();
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {
+                buf.clear();
+                continue;
+            }
+            Err(err) => return Err(Error::xml(err, src.error_position())),
+        };
+
+        let name = e.start.local_name().into_inner().to_vec();
+        let path = open.last().map_or_else(String::new, |o| o.path.clone());
+
+        if !e.is_fb2_ns() {
+            skip_depth = usize::from(!is_empty);
+        } else if let Some(parent) = open.last_mut() {
+            parent.child(&name, &mut violations);
+        } else if name != b"FictionBook" {
+            violations.push(Violation {
+                path: "/".into(),
+                kind: ViolationKind::Unexpected(String::from_utf8_lossy(&name).into_owned()),
+            });
+        }
+
+        if e.is_fb2_ns() {
+            let parent = path.rsplit('/').next().unwrap_or("").as_bytes();
+            let o = Open {
+                path: format!("{}/{}", path, String::from_utf8_lossy(&name)),
+                model: schema::model(parent, &name),
+                group: 0,
+                count: 0,
+                missing: Vec::new(),
+                is_text_reported: false,
+            };
+            if is_empty {
+                o.check_missing(&mut violations);
+            } else {
+                open.push(o);
+            }
+        }
+        buf.clear();
+    }
+
+    Ok(violations)
+}
+
+/// An open FictionBook tag.
+struct Open {
+    path: String,
+    /// A content model, if the tag is known.
+    model: Option<&'static Model>,
+    /// Index of the current group of the model.
+    group: usize,
+    /// Count of children in the current group.
+    count: u8,
+    /// Skipped groups reported as missing, with indices of their violations.
+    missing: Vec<(usize, usize)>,
+    is_text_reported: bool,
+}
+
+impl Open {
+    fn child(&mut self, name: &[u8], violations: &mut Vec<Violation>) {
+        let Some(model) = self.model else {
+            return;
+        };
+        let groups = model.groups;
+        let mut missing: Vec<usize> = Vec::new();
+        let mut count = self.count;
+
+        for (i, g) in groups.iter().enumerate().skip(self.group) {
+            if g.contains(name) && !g.is_full(count) {
+                for j in missing {
+                    self.missing.push((j, violations.len()));
+                    self.push(violations, ViolationKind::Missing(group_names(j, model)));
+                }
+                self.group = i;
+                self.count = count.saturating_add(1);
+                return;
+            }
+            if count < g.min {
+                missing.push(i);
+            }
+            count = 0;
+        }
+
+        let name = String::from_utf8_lossy(name).into_owned();
+        let misplaced = groups[..self.group.min(groups.len())]
+            .iter()
+            .position(|g| g.contains(name.as_bytes()));
+        let kind = if groups
+            .get(self.group)
+            .is_some_and(|g| g.contains(name.as_bytes()))
+        {
+            ViolationKind::TooMany(name)
+        } else if let Some(i) = misplaced {
+            // A tag out of order is misplaced rather than missing.
+            if let Some(k) = self.missing.iter().position(|&(j, _)| j == i) {
+                let (_, v) = self.missing.remove(k);
+                violations[v].kind = ViolationKind::Misplaced(name);
+                return;
+            }
+            ViolationKind::Misplaced(name)
+        } else {
+            ViolationKind::Unexpected(name)
+        };
+        self.push(violations, kind);
+    }
+
+    fn check_missing(&self, violations: &mut Vec<Violation>) {
+        let Some(model) = self.model else {
+            return;
+        };
+        let mut count = self.count;
+        for (i, g) in model.groups.iter().enumerate().skip(self.group) {
+            if count < g.min {
+                self.push(violations, ViolationKind::Missing(group_names(i, model)));
+            }
+            count = 0;
+        }
+    }
+
+    fn push(&self, violations: &mut Vec<Violation>, kind: ViolationKind) {
+        violations.push(Violation {
+            path: self.path.clone(),
+            kind,
+        });
+    }
+}
+
+fn text(open: &mut [Open], violations: &mut Vec<Violation>) {
+    if let Some(o) = open.last_mut() {
+        if o.model.is_some_and(|m| !m.text) && !o.is_text_reported {
+            o.is_text_reported = true;
+            o.push(violations, ViolationKind::Text);
+        }
+    }
+}
+
+fn group_names(i: usize, model: &Model) -> String {
+    let names: Vec<_> = model.groups[i]
+        .names
+        .iter()
+        .map(|n| String::from_utf8_lossy(n))
+        .collect();
+    names.join("|")
+}
@@ -0,0 +1,301 @@
+/// A content model of a FictionBook tag.
+pub(super) struct Model {
+    /// Non-whitespace text is allowed.
+    pub text: bool,
+    /// Child tags, as a sequence of groups.
+    pub groups: &'static [Group],
+}
+
+/// Child tags allowed at one position of a sequence.
+pub(super) struct Group {
+    pub names: &'static [&'static [u8]],
+    pub min: u8,
+    pub max: u8,
+}
+
+const UNBOUNDED: u8 = u8::MAX;
+
+const fn one(names: &'static [&'static [u8]]) -> Group {
+    Group {
+        names,
+        min: 1,
+        max: 1,
+    }
+}
+
+const fn opt(names: &'static [&'static [u8]]) -> Group {
+    Group {
+        names,
+        min: 0,
+        max: 1,
+    }
+}
+
+const fn any(names: &'static [&'static [u8]]) -> Group {
+    Group {
+        names,
+        min: 0,
+        max: UNBOUNDED,
+    }
+}
+
+const fn some(names: &'static [&'static [u8]]) -> Group {
+    Group {
+        names,
+        min: 1,
+        max: UNBOUNDED,
+    }
+}
+
+const TEXT: Model = Model {
+    text: true,
+    groups: &[],
+};
+
+const EMPTY: Model = Model {
+    text: false,
+    groups: &[],
+};
+
+const MIXED: Model = Model {
+    text: true,
+    groups: &[any(&[
+        b"strong",
+        b"emphasis",
+        b"style",
+        b"a",
+        b"strikethrough",
+        b"sub",
+        b"sup",
+        b"code",
+        b"image",
+    ])],
+};
+
+const FICTION_BOOK: Model = Model {
+    text: false,
+    groups: &[
+        any(&[b"stylesheet"]),
+        one(&[b"description"]),
+        some(&[b"body"]),
+        any(&[b"binary"]),
+    ],
+};
+
+const DESCRIPTION: Model = Model {
+    text: false,
+    groups: &[
+        one(&[b"title-info"]),
+        opt(&[b"src-title-info"]),
+        one(&[b"document-info"]),
+        opt(&[b"publish-info"]),
+        any(&[b"custom-info"]),
+        Group {
+            names: &[b"output"],
+            min: 0,
+            max: 2,
+        },
+    ],
+};
+
+const TITLE_INFO: Model = Model {
+    text: false,
+    groups: &[
+        some(&[b"genre"]),
+        some(&[b"author"]),
+        one(&[b"book-title"]),
+        opt(&[b"annotation"]),
+        opt(&[b"keywords"]),
+        opt(&[b"date"]),
+        opt(&[b"coverpage"]),
+        one(&[b"lang"]),
+        opt(&[b"src-lang"]),
+        any(&[b"translator"]),
+        any(&[b"sequence"]),
+    ],
+};
+
+const DOCUMENT_INFO: Model = Model {
+    text: false,
+    groups: &[
+        some(&[b"author"]),
+        opt(&[b"program-used"]),
+        one(&[b"date"]),
+        any(&[b"src-url"]),
+        opt(&[b"src-ocr"]),
+        one(&[b"id"]),
+        one(&[b"version"]),
+        opt(&[b"history"]),
+        any(&[b"publisher"]),
+    ],
+};
+
+const PUBLISH_INFO: Model = Model {
+    text: false,
+    groups: &[
+        opt(&[b"book-name"]),
+        opt(&[b"publisher"]),
+        opt(&[b"city"]),
+        opt(&[b"year"]),
+        opt(&[b"isbn"]),
+        any(&[b"sequence"]),
+    ],
+};
+
+const AUTHOR: Model = Model {
+    text: false,
+    groups: &[
+        opt(&[b"first-name"]),
+        opt(&[b"middle-name"]),
+        opt(&[b"last-name"]),
+        opt(&[b"nickname"]),
+        any(&[b"home-page"]),
+        any(&[b"email"]),
+        opt(&[b"id"]),
+    ],
+};
+
+const SEQUENCE: Model = Model {
+    text: false,
+    groups: &[any(&[b"sequence"])],
+};
+
+const COVERPAGE: Model = Model {
+    text: false,
+    groups: &[some(&[b"image"])],
+};
+
+const ANNOTATION: Model = Model {
+    text: false,
+    groups: &[any(&[
+        b"p",
+        b"poem",
+        b"cite",
+        b"subtitle",
+        b"table",
+        b"empty-line",
+    ])],
+};
+
+const BODY: Model = Model {
+    text: false,
+    groups: &[
+        opt(&[b"image"]),
+        opt(&[b"title"]),
+        any(&[b"epigraph"]),
+        some(&[b"section"]),
+    ],
+};
+
+const SECTION: Model = Model {
+    text: false,
+    groups: &[
+        opt(&[b"title"]),
+        any(&[b"epigraph"]),
+        opt(&[b"image"]),
+        opt(&[b"annotation"]),
+        any(&[
+            b"section",
+            b"p",
+            b"image",
+            b"poem",
+            b"subtitle",
+            b"cite",
+            b"empty-line",
+            b"table",
+        ]),
+    ],
+};
+
+const TITLE: Model = Model {
+    text: false,
+    groups: &[any(&[b"p", b"empty-line"])],
+};
+
+const EPIGRAPH: Model = Model {
+    text: false,
+    groups: &[
+        any(&[b"p", b"poem", b"cite", b"empty-line"]),
+        any(&[b"text-author"]),
+    ],
+};
+
+const CITE: Model = Model {
+    text: false,
+    groups: &[
+        any(&[b"p", b"poem", b"cite", b"subtitle", b"table", b"empty-line"]),
+        any(&[b"text-author"]),
+    ],
+};
+
+const POEM: Model = Model {
+    text: false,
+    groups: &[
+        opt(&[b"title"]),
+        any(&[b"epigraph"]),
+        some(&[b"subtitle", b"stanza"]),
+        any(&[b"text-author"]),
+        opt(&[b"date"]),
+    ],
+};
+
+const STANZA: Model = Model {
+    text: false,
+    groups: &[opt(&[b"title"]), opt(&[b"subtitle"]), some(&[b"v"])],
+};
+
+const TABLE: Model = Model {
+    text: false,
+    groups: &[some(&[b"tr"])],
+};
+
+const TR: Model = Model {
+    text: false,
+    groups: &[some(&[b"th", b"td"])],
+};
+
+impl Group {
+    pub(super) fn contains(&self, name: &[u8]) -> bool {
+        self.names.contains(&name)
+    }
+
+    pub(super) fn is_full(&self, count: u8) -> bool {
+        self.max != UNBOUNDED && count >= self.max
+    }
+}
+
+/// Returns a content model of a FictionBook tag in the parent, if it is known.
+pub(super) fn model(parent: &[u8], name: &[u8]) -> Option<&'static Model> {
+    let model = match name {
+        b"FictionBook" => &FICTION_BOOK,
+        b"description" => &DESCRIPTION,
+        b"title-info" | b"src-title-info" => &TITLE_INFO,
+        b"document-info" => &DOCUMENT_INFO,
+        b"publish-info" => &PUBLISH_INFO,
+        b"author" | b"translator" => &AUTHOR,
+        b"publisher" if parent == b"document-info" => &AUTHOR,
+        b"sequence" => &SEQUENCE,
+        b"coverpage" => &COVERPAGE,
+        b"annotation" | b"history" => &ANNOTATION,
+        b"body" => &BODY,
+        b"section" => &SECTION,
+        b"title" => &TITLE,
+        b"epigraph" => &EPIGRAPH,
+        b"cite" => &CITE,
+        b"poem" => &POEM,
+        b"stanza" => &STANZA,
+        b"table" => &TABLE,
+        b"tr" => &TR,
+        b"image" | b"empty-line" => &EMPTY,
+        b"p" | b"v" | b"subtitle" | b"text-author" | b"th" | b"td" | b"strong" | b"emphasis"
+        | b"style" | b"a" | b"strikethrough" | b"sub" | b"sup" | b"code" => &MIXED,
+        b"stylesheet" | b"binary" | b"custom-info" | b"genre" | b"first-name" | b"middle-name"
+        | b"last-name" | b"nickname" | b"home-page" | b"email" | b"id" | b"book-title"
+        | b"keywords" | b"date" | b"lang" | b"src-lang" | b"program-used" | b"src-url"
+        | b"src-ocr" | b"version" | b"publisher" | b"book-name" | b"city" | b"year" | b"isbn" => {
+            &TEXT
+        }
+        _ => return None,
+    };
+    Some(model)
+}
@@ -0,0 +1,119 @@
+use crate::Msg;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1251};
+use lazy_regex::{bytes_regex, regex::bytes::Captures};
+use log::warn;
+use std::{borrow::Cow, fmt};
+
+/// Encoding used for an XML which is not valid UTF-8, but declared so (or not
+/// declared at all).
+pub(crate) const FALLBACK_ENCODING: &Encoding = WINDOWS_1251;
+
+/// Detects an encoding of an XML by a BOM or the XML declaration, checking it
+/// as described in [`xml_to_utf8`]. Returns a WHATWG name of the encoding,
+/// such as `windows-1251`.
+pub fn detect_xml_encoding(xml: &[u8]) -> &'static str {
+    match Encoding::for_bom(xml) {
+        Some((encoding, _)) => encoding.name(),
+        None => {
+            let (encoding, misdeclared) = detect_encoding(xml);
+            if let Some(m) = misdeclared {
+                warn!("{} {}", Msg::MisdeclaredEncoding, m);
+            }
+            encoding.name()
+        }
+    }
+}
+
+/// Decodes an XML to UTF-8, rewriting `encoding` of the XML declaration to `utf-8`.
+///
+/// A mis-declared XML is decoded gracefully: a valid non-ASCII UTF-8 is kept as
+/// UTF-8 whatever is declared, and an invalid UTF-8 declared as UTF-8 (or not
+/// declared) is decoded as `windows-1251`. Undecodable bytes are replaced
+/// by U+FFFD.
+pub fn xml_to_utf8(xml: &[u8]) -> Cow<'_, [u8]> {
+    let (xml, misdeclared) = decode_xml(xml);
+    if let Some(m) = misdeclared {
+        warn!("{} {}", Msg::MisdeclaredEncoding, m);
+    }
+    xml
+}
+
+/// Decodes an XML as [`xml_to_utf8`] does, returning a mis-declared encoding
+/// instead of warning about it.
+pub(crate) fn decode_xml(xml: &[u8]) -> (Cow<'_, [u8]>, Option<Misdeclared>) {
+    let (encoding, body, misdeclared) = match Encoding::for_bom(xml) {
+        Some((encoding, bom_len)) => (encoding, &xml[bom_len..], None),
+        None => {
+            let (encoding, misdeclared) = detect_encoding(xml);
+            (encoding, xml, misdeclared)
+        }
+    };
+
+    let decoded = match encoding.decode_without_bom_handling(body).0 {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    };
+
+    let xml = match bytes_regex!(r#"^(<\?xml\s[^>]*?\bencoding\s*=\s*)("[^"]*"|'[^']*')"#)
+        .replace(&decoded, |c: &Captures| [&c[1], b"\"utf-8\""].concat())
+    {
+        Cow::Borrowed(_) => decoded,
+        Cow::Owned(s) if s == *decoded => decoded,
+        Cow::Owned(s) => Cow::Owned(s),
+    };
+    (xml, misdeclared)
+}
+
+/// An encoding declared by an XML, which differs from the detected one.
+/// Displayed as `'utf-8', decoding as 'windows-1251'`.
+pub(crate) struct Misdeclared {
+    declared: String,
+    detected: &'static Encoding,
+}
+
+impl fmt::Display for Misdeclared {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}', {} '{}'",
+            self.declared,
+            Msg::Decoding,
+            self.detected.name()
+        )
+    }
+}
+
+/// Detects an encoding of an XML without BOM, checking the declared one.
+fn detect_encoding(xml: &[u8]) -> (&'static Encoding, Option<Misdeclared>) {
+    let declared = declared_encoding(xml);
+    let encoding = declared.and_then(Encoding::for_label);
+    let is_utf8 = std::str::from_utf8(xml).is_ok();
+
+    let detected = match encoding {
+        Some(e) if e != UTF_8 && e.is_ascii_compatible() => {
+            if is_utf8 && !xml.is_ascii() {
+                UTF_8
+            } else {
+                e
+            }
+        }
+        _ if is_utf8 => UTF_8,
+        _ => FALLBACK_ENCODING,
+    };
+
+    let misdeclared = declared
+        .filter(|_| encoding != Some(detected))
+        .map(|label| Misdeclared {
+            declared: String::from_utf8_lossy(label).into_owned(),
+            detected,
+        });
+
+    (detected, misdeclared)
+}
+
+/// Returns `encoding` of the XML declaration.
+fn declared_encoding(xml: &[u8]) -> Option<&[u8]> {
+    let caps =
+        bytes_regex!(r#"^<\?xml\s[^>]*?\bencoding\s*=\s*(?:"([^"]*)"|'([^']*)')"#).captures(xml)?;
+    caps.get(1).or_else(|| caps.get(2)).map(|m| m.as_bytes())
+}
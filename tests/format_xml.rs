@@ -0,0 +1,53 @@
+use fb2_clean::*;
+use quick_xml::{NsReader, Writer};
+
+const BOOK: &str = concat!(
+    "<FictionBook>\n <body>\n  <section>\n   <p>a <emphasis>b</emphasis> <strong>c</strong></p>",
+    "\n   <empty-line/>\n   <p> </p>\n  </section>\n </body>\n</FictionBook>",
+);
+
+fn format(xml: &str, style: OutputStyle) -> String {
+    let mut w = match style {
+        OutputStyle::Pretty => Writer::new_with_indent(Vec::new(), b' ', 2),
+        _ => Writer::new(Vec::new()),
+    };
+    format_xml(&mut NsReader::from_str(xml), &mut w, style).unwrap();
+    String::from_utf8(w.into_inner()).unwrap()
+}
+
+#[test]
+fn preserve() {
+    assert_eq!(BOOK, format(BOOK, OutputStyle::Preserve));
+}
+
+#[test]
+fn minify() {
+    assert_eq!(
+        "<FictionBook><body><section><p>a <emphasis>b</emphasis> <strong>c</strong></p>\
+         <empty-line/><p> </p></section></body></FictionBook>",
+        format(BOOK, OutputStyle::Minify)
+    );
+}
+
+#[test]
+fn pretty() {
+    assert_eq!(
+        "<FictionBook>\n  <body>\n    <section>\n      <p>a <emphasis>b</emphasis> <strong>c</strong></p>\
+         \n      <empty-line/>\n      <p> </p>\n    </section>\n  </body>\n</FictionBook>\n",
+        format(BOOK, OutputStyle::Pretty)
+    );
+    let minified = format(BOOK, OutputStyle::Minify);
+    assert_eq!(
+        format(BOOK, OutputStyle::Pretty),
+        format(&minified, OutputStyle::Pretty)
+    );
+}
+
+#[test]
+fn style_from_str() {
+    for style in OutputStyle::ALL {
+        assert_eq!(Ok(style), style.as_str().parse());
+        assert_eq!(Ok(style), style.to_string().to_uppercase().parse());
+    }
+    assert!("indent".parse::<OutputStyle>().is_err());
+}
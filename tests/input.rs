@@ -3,7 +3,7 @@ mod common;
 
 use common::*;
 use fb2_clean::*;
-use std::collections::HashSet;
+use std::{collections::HashSet, path::Path};
 
 #[test]
 fn iter_dir() {
@@ -37,3 +37,195 @@ fn iter_dir_upper() {
     }
     assert!(iter.next().is_none());
 }
+
+#[test]
+fn new_errors() {
+    match Input::new(data("dummy")) {
+        Err(Error::UnsupportedExtension(p)) => assert_eq!(data("dummy"), p),
+        r => panic!("unexpected {:?}", r),
+    }
+    match Input::new(data("missing.fb2")) {
+        Err(e @ Error::Io { .. }) => assert_eq!(Some(&*data("missing.fb2")), e.path()),
+        r => panic!("unexpected {:?}", r),
+    }
+}
+
+#[test]
+fn file_types() {
+    let dir = temp("input_file_types");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    for (ty, f) in [
+        (InputFileType::Fb2, "a.fb2"),
+        (InputFileType::Fb2Zip, "a.fb2.zip"),
+        (InputFileType::Fbz, "a.FBZ"),
+        (InputFileType::Fb2Gz, "a.fb2.gz"),
+        (InputFileType::Fb2Bz2, "a.fb2.bz2"),
+        (InputFileType::Fb2Xz, "a.fb2.xz"),
+        (InputFileType::Zip, "fb2-1-2.zip"),
+    ] {
+        let path = dir.join(f);
+        std::fs::write(&path, "").unwrap();
+        match Input::new(&path) {
+            Ok(Input::File(f)) => assert_eq!(ty, f.ty),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+    for f in ["a.gz", "a.xz", "afbz", "a.fb2.7z"] {
+        let path = dir.join(f);
+        std::fs::write(&path, "").unwrap();
+        assert!(Input::new(&path).is_err());
+    }
+}
+
+#[test]
+fn file_type_from_str() {
+    assert_eq!(Ok(InputFileType::Fb2Gz), "fb2.gz".parse());
+    assert_eq!(Ok(InputFileType::Fbz), ".FBZ".parse());
+    assert_eq!(Ok(InputFileType::Fb2), "fb2".parse());
+    assert!("zip".parse::<InputFileType>().is_err());
+    assert!("gz".parse::<InputFileType>().is_err());
+}
+
+fn sniff_dir() -> std::path::PathBuf {
+    use std::io::Write;
+
+    let dir = temp("input_sniff").to_path_buf();
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let fb2 = "<?xml version=\"1.0\"?>\n<!-- c --><FictionBook xmlns=\"http://www.gribuser.ru/xml/fictionbook/2.0\"/>";
+    std::fs::write(dir.join("book.xml"), fb2).unwrap();
+    std::fs::write(dir.join("book"), "<FictionBook/>").unwrap();
+    let mut utf16 = vec![0xFF, 0xFE];
+    utf16.extend(fb2.encode_utf16().flat_map(u16::to_le_bytes));
+    std::fs::write(dir.join("utf16.txt"), utf16).unwrap();
+    std::fs::copy(data("book.fb2.zip"), dir.join("book.zip")).unwrap();
+    std::fs::copy(data("book.fb2.zip"), dir.join("book.fbz")).unwrap();
+
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(fb2.as_bytes()).unwrap();
+    std::fs::write(dir.join("gz.fb2"), gz.finish().unwrap()).unwrap();
+
+    std::fs::write(dir.join("cover.jpg"), b"\xff\xd8\xff\xe0 not a book").unwrap();
+    std::fs::write(dir.join("page.xml"), "<html><FictionBook/></html>").unwrap();
+    std::fs::write(dir.join("fake.fb2"), "text <FictionBook/>").unwrap();
+    std::fs::write(dir.join("fake.fb2.zip"), "PK\x03\x04 broken").unwrap();
+    for (name, entries) in [
+        ("fake.zip", &[("a.fb2", "notes")][..]),
+        ("many.fb2.zip", &[("a.fb2", fb2), ("b.fb2", fb2)]),
+    ] {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(dir.join(name)).unwrap());
+        for (entry, content) in entries {
+            zip.start_file(*entry, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    std::fs::create_dir_all(dir.join("sub.fb2")).unwrap();
+    dir
+}
+
+#[test]
+fn sniff() {
+    let dir = sniff_dir();
+    for (f, exp) in [
+        ("book.xml", Some(InputFileType::Fb2)),
+        ("book", Some(InputFileType::Fb2)),
+        ("utf16.txt", Some(InputFileType::Fb2)),
+        ("book.zip", Some(InputFileType::Zip)),
+        ("book.fbz", Some(InputFileType::Fbz)),
+        ("gz.fb2", Some(InputFileType::Fb2Gz)),
+        ("cover.jpg", None),
+        ("page.xml", None),
+        ("fake.fb2", None),
+        ("fake.fb2.zip", None),
+        ("fake.zip", None),
+        ("many.fb2.zip", Some(InputFileType::Zip)),
+    ] {
+        assert_eq!(exp, InputFileType::sniff(dir.join(f)).unwrap(), "{}", f);
+    }
+
+    let i = Input::Dir(dir.clone().into());
+    let got: HashSet<_> = i.iter_sniffed().map(|f| f.path).collect();
+    let exp: HashSet<_> = [
+        "book.xml",
+        "book",
+        "utf16.txt",
+        "book.zip",
+        "book.fbz",
+        "gz.fb2",
+        "many.fb2.zip",
+    ]
+    .iter()
+    .map(|f| dir.join(f).into_boxed_path())
+    .collect();
+    assert_eq!(exp, got);
+}
+
+#[test]
+fn new_sniffed() {
+    let dir = sniff_dir();
+    match Input::new_sniffed(dir.join("book")) {
+        Ok(Input::File(f)) => assert_eq!(InputFileType::Fb2, f.ty),
+        r => panic!("unexpected {:?}", r),
+    }
+    match Input::new_sniffed(dir.join("fake.fb2")) {
+        Err(Error::NotABook(p)) => assert_eq!(&*dir.join("fake.fb2"), &*p),
+        r => panic!("unexpected {:?}", r),
+    }
+    assert!(matches!(Input::new_sniffed(&dir), Ok(Input::Dir(_))));
+}
+
+fn subdirs(xs: &[InputSource]) -> Vec<&Path> {
+    xs.iter().map(|x| &*x.subdir).collect()
+}
+
+#[test]
+fn glob() {
+    let xs = InputSource::new(data("recursive/**/*.fb2"), false).unwrap();
+    let exp = ["1/2/3", "1", ""];
+    assert_eq!(exp.map(Path::new).to_vec(), subdirs(&xs));
+    let exp = Input::File(InputFile {
+        ty: InputFileType::Fb2,
+        path: data("recursive/1/2/3/dummy.fb2"),
+    });
+    assert_eq!(exp, xs[0].input);
+
+    let xs = InputSource::new(data("recursive/*"), false).unwrap();
+    assert_eq!(Input::Dir(data("recursive/1")), xs[0].input);
+    assert_eq!(Path::new("1"), &*xs[0].subdir);
+    assert_eq!(2, xs.len());
+
+    let xs = InputSource::new(data("dumm?.fb[2]"), false).unwrap();
+    assert_eq!(1, xs.len());
+    assert!(InputSource::new(data("*.epub"), false).is_err());
+}
+
+#[test]
+fn read_list() {
+    let dir = temp("input_read_list");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let list = dir.join("list");
+    let abs = data("dummy.fb2");
+    let lines = format!(
+        "tests/data/recursive/1/dummy.fb2\r\n./tests/data/recursive\n\n{}\n",
+        abs.display()
+    );
+    std::fs::write(&list, lines).unwrap();
+    let xs = InputSource::read_list(&list, false).unwrap();
+    let exp = ["tests/data/recursive/1", "tests/data/recursive", ""];
+    assert_eq!(exp.map(Path::new).to_vec(), subdirs(&xs));
+    assert_eq!(Input::Dir(data("recursive")), xs[1].input);
+
+    std::fs::write(&list, "tests/data/dummy.fb2\0tests/data/dummy.fb2.zip").unwrap();
+    assert_eq!(2, InputSource::read_list(&list, false).unwrap().len());
+
+    std::fs::write(&list, "tests/data/missing.fb2").unwrap();
+    assert!(InputSource::read_list(&list, false).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,200 @@
+#[allow(unused)]
+mod common;
+
+use common::*;
+use fb2_clean::{Error, Lang, Msg, ReportFormat, cli};
+use std::{fs, sync::Mutex};
+
+fn report_json(dir: &str, format: ReportFormat) -> String {
+    let dir = temp(dir);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy(data("book.fb2.zip"), dir.join("book.fb2.zip")).unwrap();
+    fs::write(dir.join("broken.fb2"), "<FictionBook><p></i></FictionBook>").unwrap();
+
+    let i = dir.to_str().unwrap().to_owned();
+    let mut c = cfg(&["-i", &i]);
+    c.output.create_dirs().unwrap();
+    let r = c.run_report().unwrap();
+
+    let mut out = Vec::new();
+    cli::write_report(&mut out, &r, format).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn ndjson() {
+    let s = report_json("json_ndjson", ReportFormat::Ndjson);
+    let lines: Vec<_> = s.lines().collect();
+    assert_eq!(3, lines.len());
+
+    assert!(lines[0].starts_with(r#"{"event":"book","src":""#));
+    assert!(lines[0].contains(r#""status":"cleaned""#));
+    assert!(lines[0].contains(r#""coverpage":1"#));
+    assert!(lines[0].ends_with(r#""error":null}"#));
+
+    assert!(lines[1].contains(r#""status":"failed""#));
+    assert!(lines[1].contains(r#""output_size":null"#));
+    assert!(lines[1].contains(r#""error":{"kind":"xml","path":""#));
+
+    assert!(lines[2].starts_with(
+        r#"{"event":"summary","books":2,"cleaned":1,"skipped":0,"failed":1,"overwritten":0,"#
+    ));
+}
+
+#[test]
+fn json() {
+    let s = report_json("json_json", ReportFormat::Json);
+    assert_eq!(1, s.lines().count());
+    assert!(s.starts_with(r#"{"books":[{"src":""#));
+    assert!(s.contains(r#"}],"summary":{"books":2,"cleaned":1,"#));
+    assert!(s.ends_with("},\"error\":null}\n"));
+}
+
+#[test]
+fn partial_report() {
+    let dir = temp("json_partial");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy(data("book.fb2.zip"), dir.join("a.fb2.zip")).unwrap();
+    fs::write(dir.join("b.fb2"), "<FictionBook><p></i></FictionBook>").unwrap();
+
+    let (a, b) = (dir.join("a.fb2.zip"), dir.join("b.fb2"));
+    let o = dir.join("cleaned");
+    let mut c = cfg(&[
+        "-e",
+        "-i",
+        a.to_str().unwrap(),
+        "-i",
+        b.to_str().unwrap(),
+        "-o",
+        o.to_str().unwrap(),
+    ]);
+    c.output.create_dirs().unwrap();
+    let r = c.run_report().unwrap();
+    assert_eq!(1, r.books.len());
+    assert!(matches!(r.error, Some(Error::Xml { .. })));
+
+    let mut out = Vec::new();
+    cli::write_report(&mut out, &r, ReportFormat::Ndjson).unwrap();
+    let s = String::from_utf8(out).unwrap();
+    let lines: Vec<_> = s.lines().collect();
+    assert_eq!(3, lines.len());
+    assert!(lines[0].contains(r#""status":"cleaned""#));
+    assert!(lines[1].starts_with(r#"{"event":"summary","books":1,"#));
+    assert!(lines[2].starts_with(r#"{"event":"error","error":{"kind":"xml","#));
+
+    let mut out = Vec::new();
+    cli::write_report(&mut out, &r, ReportFormat::Json).unwrap();
+    let s = String::from_utf8(out).unwrap();
+    assert!(s.contains(r#"},"error":{"kind":"xml","#));
+}
+
+#[test]
+fn streamed_events() {
+    let dir = temp("json_streamed");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy(data("book.fb2.zip"), dir.join("book.fb2.zip")).unwrap();
+    fs::write(dir.join("broken.fb2"), "<FictionBook><p></i></FictionBook>").unwrap();
+
+    let i = dir.to_str().unwrap().to_owned();
+    let events = Mutex::new(Vec::new());
+    let mut c = cfg(&["-i", &i, "--force"]);
+    c.output.create_dirs().unwrap();
+    let r = c
+        .run_report_with(|b| cli::write_book_event(&mut *events.lock().unwrap(), b).unwrap())
+        .unwrap();
+
+    let events = String::from_utf8(events.into_inner().unwrap()).unwrap();
+    let mut streamed: Vec<_> = events.lines().collect();
+    streamed.sort();
+    assert!(streamed[0].contains(r#""status":"overwritten""#));
+    assert!(streamed[1].contains(r#""status":"failed""#));
+
+    let mut out = Vec::new();
+    cli::write_report(&mut out, &r, ReportFormat::Ndjson).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let mut books: Vec<_> = out.lines().take(2).collect();
+    books.sort();
+    assert_eq!(books, streamed);
+}
+
+#[test]
+fn text_is_empty() {
+    assert_eq!("", report_json("json_text", ReportFormat::Text));
+}
+
+#[test]
+fn error() {
+    let e = Error::InvalidValue("a \"b\"\\\n".into());
+    let mut out = Vec::new();
+    cli::write_error(&mut out, &e, ReportFormat::Ndjson).unwrap();
+    assert_eq!(
+        "{\"event\":\"error\",\"error\":{\"kind\":\"invalid_value\",\"path\":null,\"message\":\"a \\\"b\\\"\\\\\\n\"}}\n",
+        String::from_utf8(out).unwrap()
+    );
+
+    let mut out = Vec::new();
+    cli::write_error(&mut out, &e, ReportFormat::Json).unwrap();
+    assert!(
+        String::from_utf8(out)
+            .unwrap()
+            .starts_with(r#"{"error":{"kind":"#)
+    );
+}
+
+#[test]
+fn keys_do_not_depend_on_lang() {
+    let _ = Msg::set_lang(Lang::Rus);
+    let s = report_json("json_lang", ReportFormat::Ndjson);
+    for key in [
+        r#""event":"book""#,
+        r#""status":"failed""#,
+        r#""error":{"kind":"xml","path":""#,
+        r#""message":""#,
+        r#""event":"summary""#,
+    ] {
+        assert!(s.contains(key), "{}", key);
+    }
+}
+
+#[test]
+fn entries_report() {
+    use std::io::Write;
+
+    let dir = temp("json_entries");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let i = dir.join("books.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&i).unwrap());
+    for (name, xml) in [
+        (
+            "a.fb2",
+            &b"<FictionBook><body><p>x</p></body></FictionBook>"[..],
+        ),
+        ("b.fb2", b"<FictionBook><p></i>"),
+    ] {
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(xml).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let r = cfg(&["-i", i.to_str().unwrap(), "--dry-run"])
+        .run_report()
+        .unwrap();
+    let mut out = Vec::new();
+    cli::write_report(&mut out, &r, ReportFormat::Json).unwrap();
+    let s = String::from_utf8(out).unwrap();
+    assert!(
+        s.contains(r#""failed_entries":1,"entries":[{"name":"a.fb2","status":"dry_run","#),
+        "{}",
+        s
+    );
+    assert!(
+        s.contains(r#"{"name":"b.fb2","status":"failed","removed":{},"removed_bytes":0,"error":{"kind":"xml","#),
+        "{}",
+        s
+    );
+}
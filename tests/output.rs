@@ -21,3 +21,11 @@ fn create_and_remove_dirs() {
     o.remove_created_dirs();
     assert!(!dir.exists());
 }
+
+#[test]
+fn new_not_a_directory() {
+    match Output::new(data("dummy.fb2")) {
+        Err(Error::NotADirectory(p)) => assert_eq!(data("dummy.fb2"), p),
+        r => panic!("unexpected {:?}", r),
+    }
+}
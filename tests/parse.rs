@@ -96,17 +96,349 @@ fn recursive() {
 
 #[test]
 fn tags() {
-    use indexmap::IndexSet;
-    for tags in ["a", "b", "a,b", "a,c"] {
-        let exp: IndexSet<Box<[u8]>> = tags.split(',').map(|t| t.as_bytes().into()).collect();
+    for tags in [
+        "a",
+        "b",
+        "a,b",
+        "a,c",
+        "a[x],b[y=z][w]",
+        "a[x=\"1,2\"]",
+        "a/b,a//b[x=y/z]",
+    ] {
+        let exp = Tags::parse_list(tags).unwrap();
         let mut c = cfg(&["--tags", tags]);
 
-        assert_eq!(c.tags, Tags(exp));
+        assert_eq!(c.tags.list, exp);
+        assert_eq!(c.tags.to_string(), tags);
+        assert!(!c.tags.keep);
         c.tags = Default::default();
         assert_eq!(c, cfg(&[]));
     }
 }
 
+#[test]
+fn unwrap_tags() {
+    let exp = Tags::parse_list("a,style").unwrap();
+    let mut c = cfg(&["--unwrap-tags", "a,style"]);
+    assert_eq!(c.tags.unwrap, exp);
+    c.tags.unwrap.clear();
+    assert_eq!(c, cfg(&[]));
+}
+
+#[test]
+fn invalid_tags() {
+    for tags in ["a[", "a[x", "[x]", "a[=y]", "a[x]b", "a/", "a/[x]"] {
+        Config::try_parse_from(["x", "--tags", tags]).unwrap_err();
+    }
+}
+
+#[test]
+fn local_names() {
+    let mut c = cfg(&["--local-names"]);
+    assert!(c.tags.local_names);
+    c.tags.local_names = false;
+    assert_eq!(c, cfg(&[]));
+}
+
+#[test]
+fn fix_refs() {
+    let mut c = cfg(&["--fix-refs"]);
+    assert!(c.tags.fix_refs);
+    c.tags.fix_refs = false;
+    assert_eq!(c, cfg(&[]));
+}
+
+#[test]
+fn keep_cover() {
+    let mut c = cfg(&["--keep-cover"]);
+    assert!(c.tags.keep_cover);
+    assert!(c.tags.needs_refs());
+    c.tags.keep_cover = false;
+    assert_eq!(c, cfg(&[]));
+}
+
+#[test]
+fn max_binary_size() {
+    for (size, bytes) in [("0", 0), ("200", 200), ("200K", 204800), ("1mb", 1048576)] {
+        let mut c = cfg(&["--max-binary-size", size]);
+        assert_eq!(Some(bytes), c.tags.max_binary_size);
+        assert!(c.tags.needs_refs());
+        c.tags.max_binary_size = None;
+        assert_eq!(c, cfg(&[]));
+    }
+}
+
+#[test]
+fn invalid_max_binary_size() {
+    for size in ["", "K", "-1", "1T", "99999999999G"] {
+        Config::try_parse_from(["x", "--max-binary-size", size]).unwrap_err();
+    }
+}
+
+#[cfg(feature = "images")]
+#[test]
+fn images() {
+    let mut c = cfg(&[
+        "--max-width",
+        "600",
+        "--max-height",
+        "800",
+        "--grayscale",
+        "--jpeg-quality",
+        "70",
+    ]);
+    assert_eq!(Some(600), c.images.max_width);
+    assert_eq!(Some(800), c.images.max_height);
+    assert!(c.images.grayscale);
+    assert_eq!(Some(70), c.images.quality);
+    assert!(c.images.is_enabled());
+    assert!(c.tags.list.is_empty());
+    c.images = Images::default();
+    c.tags = Tags::default();
+    assert_eq!(c, cfg(&[]));
+
+    let c = cfg(&["--grayscale", "-t", "binary"]);
+    assert_eq!("binary", c.tags.to_string());
+
+    for q in ["0", "101"] {
+        Config::try_parse_from(["x", "--jpeg-quality", q]).unwrap_err();
+    }
+}
+
+#[test]
+fn strip_attrs() {
+    for s in [
+        "style",
+        "xml:lang",
+        "p@id",
+        "body//p[id]@style=\"a,b\"",
+        "p@id,style",
+    ] {
+        let mut c = cfg(&["--strip-attrs", s]);
+        let list: Vec<String> = c.tags.strip_attrs.iter().map(|a| a.to_string()).collect();
+        assert_eq!(s, list.join(","));
+        c.tags.strip_attrs.clear();
+        assert_eq!(c, cfg(&[]));
+    }
+    for s in ["@id", "p@", "p[@id", "p@id,@"] {
+        Config::try_parse_from(["x", "--strip-attrs", s]).unwrap_err();
+    }
+}
+
+#[test]
+fn prune_empty() {
+    for (args, list) in [
+        (&["--prune-empty"][..], "p,section"),
+        (&["--prune-empty", "p,empty-line"], "p,empty-line"),
+    ] {
+        let mut c = cfg(args);
+        let s: Vec<String> = c.tags.prune_empty.iter().map(|s| s.to_string()).collect();
+        assert_eq!(list, s.join(","));
+        c.tags.prune_empty.clear();
+        assert_eq!(c, cfg(&[]));
+    }
+}
+
+#[test]
+fn comments_pis_doctype() {
+    let mut c = cfg(&["--strip-comments", "--strip-pis", "--strip-doctype"]);
+    assert!(c.tags.strip_comments);
+    assert!(c.tags.strip_doctype);
+    assert_eq!(Pis::Strip, c.tags.pis);
+    c.tags.strip_comments = false;
+    c.tags.strip_doctype = false;
+    c.tags.pis = Pis::Keep;
+    assert_eq!(c, cfg(&[]));
+
+    let c = cfg(&["--keep-pis", "^xml-stylesheet"]);
+    assert_eq!("^xml-stylesheet", c.tags.pis.to_string());
+    assert_eq!(Pis::new_matching("^xml-stylesheet").unwrap(), c.tags.pis);
+
+    Config::try_parse_from(["x", "--keep-pis", "("]).unwrap_err();
+    Config::try_parse_from(["x", "--keep-pis", "a", "--strip-pis"]).unwrap_err();
+}
+
+#[test]
+fn style() {
+    for style in OutputStyle::ALL {
+        let mut c = cfg(&["--style", style.as_str()]);
+        assert_eq!(style, c.style);
+        c.style = OutputStyle::default();
+        assert_eq!(c, cfg(&[]));
+    }
+    assert_eq!(OutputStyle::Pretty, cfg(&["-s", "pretty"]).style);
+    Config::try_parse_from(["x", "--style", "indent"]).unwrap_err();
+}
+
+#[test]
+fn utf8() {
+    for arg in ["--utf8", "--to-utf8"] {
+        let mut c = cfg(&[arg]);
+        assert!(c.utf8);
+        c.utf8 = false;
+        assert_eq!(c, cfg(&[]));
+    }
+}
+
+#[test]
+fn lenient() {
+    for arg in ["--lenient", "--repair"] {
+        let mut c = cfg(&[arg]);
+        assert!(c.lenient);
+        c.lenient = false;
+        assert_eq!(c, cfg(&[]));
+    }
+}
+
+#[test]
+fn validate() {
+    assert_eq!(Validation::Off, cfg(&[]).validation);
+    assert_eq!(Validation::Warn, cfg(&["--validate"]).validation);
+    assert_eq!(Validation::Fail, cfg(&["--validate", "fail"]).validation);
+    assert_eq!(Validation::Off, cfg(&["--validate", "OFF"]).validation);
+    Config::try_parse_from(["x", "--validate", "strict"]).unwrap_err();
+}
+
+#[test]
+fn sniff() {
+    let dir = temp("parse_sniff");
+    fs::create_dir_all(&dir).unwrap();
+    let i = dir.join("book");
+    fs::write(&i, "<FictionBook/>").unwrap();
+    let i = i.to_str().unwrap();
+
+    Config::try_parse_from(["x", "-i", i]).unwrap_err();
+    let c = cfg(&["--sniff", "-i", i]);
+    assert!(c.sniff);
+    match c.input {
+        Input::File(f) => assert_eq!(InputFileType::Fb2, f.ty),
+        i => panic!("unexpected {:?}", i),
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn stdin_stdout() {
+    let c = cfg(&["-i", "-"]);
+    assert_eq!(Input::Stdin, c.input);
+    assert_eq!(cfg(&[]).output, c.output);
+    assert!(!c.stdout);
+
+    let mut c = cfg(&["-o", "-"]);
+    assert!(c.stdout);
+    c.stdout = false;
+    assert_eq!(c, cfg(&[]));
+
+    Config::try_parse_from(["x", "-o", "-", "--format", "json"]).unwrap_err();
+}
+
+#[test]
+fn many_inputs() {
+    let (a, b) = (data("dummy.fb2"), data("recursive"));
+    let c = cfg(&["-i", a.to_str().unwrap(), "-i", b.to_str().unwrap()]);
+    let exp = vec![
+        InputSource {
+            subdir: Path::new("").into(),
+            input: Input::new(&a).unwrap(),
+        },
+        InputSource {
+            subdir: Path::new("recursive").into(),
+            input: Input::new(&b).unwrap(),
+        },
+    ];
+    assert_eq!(Input::Many(exp), c.input);
+    assert_eq!(cfg(&[]).output, c.output);
+
+    let c = cfg(&["-i", data("recursive/**/*.fb2").to_str().unwrap()]);
+    assert!(matches!(c.input, Input::Many(xs) if xs.len() == 3));
+    Config::try_parse_from(["x", "-i", data("*.epub").to_str().unwrap()]).unwrap_err();
+}
+
+#[test]
+fn same_dir_names() {
+    let dir = temp("parse_same_dir_names");
+    for d in ["a/books", "b/books", "c/books"] {
+        fs::create_dir_all(dir.join(d)).unwrap();
+    }
+    let (a, b) = (dir.join("a/books"), dir.join("b/books"));
+    let c = cfg(&[
+        "-i",
+        a.to_str().unwrap(),
+        "-i",
+        b.to_str().unwrap(),
+        "-i",
+        dir.join("c/books").to_str().unwrap(),
+        "-i",
+        a.to_str().unwrap(),
+    ]);
+    match c.input {
+        Input::Many(xs) => {
+            let subdirs: Vec<_> = xs.iter().map(|x| &*x.subdir).collect();
+            assert_eq!(
+                vec![
+                    Path::new("books"),
+                    Path::new("books-2"),
+                    Path::new("books-3"),
+                    Path::new("books"),
+                ],
+                subdirs
+            );
+        }
+        i => panic!("unexpected {:?}", i),
+    }
+}
+
+#[test]
+fn files_from() {
+    let dir = temp("parse_files_from");
+    fs::create_dir_all(&dir).unwrap();
+    let list = dir.join("list");
+    fs::write(&list, "tests/data/dummy.fb2\n").unwrap();
+
+    let c = cfg(&["--files-from", list.to_str().unwrap()]);
+    match c.input {
+        Input::Many(xs) => assert_eq!(Path::new("tests/data"), &*xs[0].subdir),
+        i => panic!("unexpected {:?}", i),
+    }
+    Config::try_parse_from(["x", "--files-from", "missing"]).unwrap_err();
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn container() {
+    assert_eq!(None, cfg(&[]).container);
+    assert_eq!(
+        Some(InputFileType::Fb2Xz),
+        cfg(&["--container", "fb2.xz"]).container
+    );
+    Config::try_parse_from(["x", "--container", "zip"]).unwrap_err();
+    Config::try_parse_from(["x", "--container", "fbz", "--zip"]).unwrap_err();
+}
+
+#[test]
+fn dry_run() {
+    let mut c = cfg(&["--dry-run"]);
+    assert!(c.dry_run);
+    c.dry_run = false;
+    assert_eq!(c, cfg(&[]));
+}
+
+#[test]
+fn format() {
+    assert_eq!(ReportFormat::Text, cfg(&[]).format);
+    assert_eq!(ReportFormat::Json, cfg(&["--format", "json"]).format);
+    assert_eq!(ReportFormat::Ndjson, cfg(&["--format", "NDJSON"]).format);
+    Config::try_parse_from(["x", "--format", "xml"]).unwrap_err();
+}
+
+#[test]
+fn keep() {
+    let mut c = cfg(&["--keep", "--tags", "body,p"]);
+    assert!(c.tags.keep);
+    c.tags.keep = false;
+    assert_eq!(c, cfg(&["--tags", "body,p"]));
+}
+
 #[test]
 fn zip() {
     let mut c = cfg(&["--zip"]);
@@ -125,7 +457,7 @@ fn unzip() {
 
 #[test]
 fn zip_unzip_conflict() {
-    Config::try_parse_from(&["x", "--zip", "--unzip"]).unwrap_err();
+    Config::try_parse_from(["x", "--zip", "--unzip"]).unwrap_err();
 }
 
 #[test]
@@ -148,7 +480,12 @@ fn exit_on_err() {
 fn aliases_io_tags() {
     let v = env!("CARGO_MANIFEST_DIR");
 
-    for xs in [["-i", "--input"], ["-o", "--output"], ["-t", "--tags"]] {
+    for xs in [
+        ["-i", "--input"],
+        ["-o", "--output"],
+        ["-t", "--tags"],
+        ["-u", "--unwrap-tags"],
+    ] {
         let first = cfg(&[xs[0], v]);
 
         for x in &xs[1..] {
@@ -161,6 +498,7 @@ fn aliases_io_tags() {
 fn aliases() {
     [
         vec!["-r", "--recursive"],
+        vec!["-k", "--keep", "--keep-only"],
         vec!["-z", "--zip"],
         vec!["-Z", "--unzip", "--no-zip"],
         vec!["-f", "--force"],
@@ -0,0 +1,125 @@
+#![cfg(feature = "images")]
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use fb2_clean::*;
+use image::{ImageFormat, RgbaImage};
+use quick_xml::{NsReader, Writer, events::Event};
+use std::io::Cursor;
+
+fn encode(img: RgbaImage) -> String {
+    let mut bytes = Vec::new();
+    img.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    STANDARD.encode(bytes)
+}
+
+/// A noisy PNG, larger than its JPEG.
+fn png(width: u32, height: u32) -> String {
+    encode(RgbaImage::from_fn(width, height, |x, y| {
+        let n = (x * 7919 + y * 104729) ^ (x * y * 31);
+        image::Rgba([200, (n % 251) as u8, (n % 241) as u8, 255])
+    }))
+}
+
+fn book(payload: &str) -> String {
+    format!(
+        "<FictionBook><body><p>a</p></body>\
+         <binary id=\"a\" content-type=\"image/png\">{}</binary></FictionBook>",
+        payload
+    )
+}
+
+fn recompress(xml: &str, args: &[&str]) -> String {
+    use clap::Parser;
+    let mut xs = vec!["x"];
+    xs.extend_from_slice(args);
+    let images = Config::try_parse_from(xs).unwrap().images;
+
+    let mut w = Writer::new(Vec::new());
+    recompress_xml_images(&mut NsReader::from_str(xml), &mut w, &images, false).unwrap();
+    String::from_utf8(w.into_inner()).unwrap()
+}
+
+fn binary(xml: &str) -> image::DynamicImage {
+    let mut r = NsReader::from_str(xml);
+    loop {
+        match r.read_event().unwrap() {
+            Event::Text(e) if !e.is_empty() && &*e != b"a" => {
+                let bytes = STANDARD.decode(&*e).unwrap();
+                assert_eq!(ImageFormat::Jpeg, image::guess_format(&bytes).unwrap());
+                return image::load_from_memory(&bytes).unwrap();
+            }
+            Event::Eof => panic!("No binary"),
+            _ => (),
+        }
+    }
+}
+
+#[test]
+fn recompress_jpeg() {
+    let xml = recompress(&book(&png(40, 20)), &["--jpeg-quality", "50"]);
+    assert!(xml.contains("<binary id=\"a\" content-type=\"image/jpeg\">"));
+    assert!(xml.starts_with("<FictionBook><body><p>a</p></body>"));
+
+    let img = binary(&xml);
+    assert_eq!((40, 20), (img.width(), img.height()));
+    assert!(img.color().has_color());
+}
+
+#[test]
+fn downscale() {
+    for (args, size) in [
+        (&["--max-width", "10"][..], (10, 5)),
+        (&["--max-height", "4"], (8, 4)),
+        (&["--max-width", "10", "--max-height", "4"], (8, 4)),
+        (&["--max-width", "100"], (40, 20)),
+    ] {
+        let img = binary(&recompress(&book(&png(40, 20)), args));
+        assert_eq!(size, (img.width(), img.height()));
+    }
+}
+
+#[test]
+fn grayscale() {
+    let img = binary(&recompress(&book(&png(40, 20)), &["--grayscale"]));
+    assert!(!img.color().has_color());
+}
+
+#[test]
+fn blend_alpha() {
+    let transparent = encode(RgbaImage::from_fn(40, 20, |x, y| {
+        let n = (x * 7919 + y * 104729) ^ (x * y * 31);
+        image::Rgba([(n % 251) as u8, (n % 241) as u8, 0, (n % 8) as u8])
+    }));
+    let img = binary(&recompress(&book(&transparent), &[])).to_rgb8();
+    assert!(img.pixels().all(|p| p.0.iter().all(|&c| c > 200)));
+}
+
+#[test]
+fn keep_larger() {
+    let xml = book(&encode(RgbaImage::new(40, 20)));
+    assert_eq!(xml, recompress(&xml, &["--jpeg-quality", "100"]));
+}
+
+#[test]
+fn local_names() {
+    let xml = book(&png(40, 20))
+        .replace("<binary", "<x:binary xmlns:x=\"y\"")
+        .replace("</binary", "</x:binary");
+    assert_eq!(xml, recompress(&xml, &[]));
+
+    let mut w = Writer::new(Vec::new());
+    let images = Images::default();
+    recompress_xml_images(&mut NsReader::from_str(&xml), &mut w, &images, true).unwrap();
+    assert!(
+        String::from_utf8(w.into_inner())
+            .unwrap()
+            .contains("image/jpeg")
+    );
+}
+
+#[test]
+fn keep_invalid() {
+    let xml = book("AAAA");
+    assert_eq!(xml, recompress(&xml, &["--grayscale"]));
+}
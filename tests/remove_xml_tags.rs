@@ -0,0 +1,356 @@
+use fb2_clean::*;
+use quick_xml::{NsReader, Writer};
+
+fn clean(xml: &str, tags: &Tags) -> String {
+    let mut w = Writer::new(Vec::new());
+    let mut r = NsReader::from_str(xml);
+    if tags.needs_refs() {
+        let refs = scan_xml_refs(&mut NsReader::from_str(xml), tags).unwrap();
+        remove_xml_tags_with_refs(&mut r, &mut w, tags, &refs).unwrap();
+    } else {
+        remove_xml_tags(&mut r, &mut w, tags).unwrap();
+    }
+    String::from_utf8(w.into_inner()).unwrap()
+}
+
+fn tags(args: &[&str]) -> Tags {
+    use clap::Parser;
+    let mut xs = vec!["x"];
+    xs.extend_from_slice(args);
+    Config::try_parse_from(xs).unwrap().tags
+}
+
+const BOOK: &str = concat!(
+    "<FictionBook><description><coverpage><image/></coverpage></description>",
+    "<body><section><p>a<image/></p><empty-line/></section></body>",
+    "<binary>AA==</binary></FictionBook>",
+);
+
+#[test]
+fn remove() {
+    assert_eq!(
+        "<FictionBook><description></description>\
+         <body><section><p>a</p><empty-line/></section></body></FictionBook>",
+        clean(BOOK, &Tags::default())
+    );
+}
+
+#[test]
+fn keep() {
+    let t = tags(&["--keep", "--tags", "body,section,p"]);
+    assert_eq!(
+        "<FictionBook><body><section><p>a</p></section></body></FictionBook>",
+        clean(BOOK, &t)
+    );
+}
+
+#[test]
+fn unwrap() {
+    let t = tags(&["--tags", "binary", "--unwrap-tags", "section,p,image"]);
+    assert_eq!(
+        "<FictionBook><description><coverpage></coverpage></description>\
+         <body>a<empty-line/></body></FictionBook>",
+        clean(BOOK, &t)
+    );
+}
+
+#[test]
+fn keep_unwrap() {
+    let t = tags(&["--keep", "--tags", "body,p", "--unwrap-tags", "section"]);
+    assert_eq!(
+        "<FictionBook><body><p>a</p></body></FictionBook>",
+        clean(BOOK, &t)
+    );
+}
+
+#[test]
+fn attr_selectors() {
+    let xml = concat!(
+        "<body><section id=\"ads\"><p>x</p></section><section id=\"1\"><p>a</p>",
+        "<p style=\"s\">b</p></section></body>",
+    );
+    assert_eq!(
+        "<body><section id=\"1\"><p>a</p></section></body>",
+        clean(xml, &tags(&["--tags", "section[id=ads],p[style]"]))
+    );
+    assert_eq!(
+        "<body><section id=\"ads\"><p>x</p></section></body>",
+        clean(xml, &tags(&["--tags", "section[id='1']"]))
+    );
+}
+
+#[test]
+fn path_selectors() {
+    let xml = concat!(
+        "<FictionBook><description><coverpage><image/></coverpage></description>",
+        "<body><image/><section><image/><p><image/></p></section></body></FictionBook>",
+    );
+    assert_eq!(
+        "<FictionBook><description><coverpage><image/></coverpage></description>\
+         <body><image/><section><p><image/></p></section></body></FictionBook>",
+        clean(xml, &tags(&["--tags", "body/section/image"]))
+    );
+    assert_eq!(
+        "<FictionBook><description><coverpage><image/></coverpage></description>\
+         <body><section><p></p></section></body></FictionBook>",
+        clean(xml, &tags(&["--tags", "body//image"]))
+    );
+    assert_eq!(
+        "<FictionBook><description><coverpage></coverpage></description>\
+         <body><image/><section><image/><p><image/></p></section></body></FictionBook>",
+        clean(xml, &tags(&["--tags", "FictionBook//coverpage/image"]))
+    );
+}
+
+#[test]
+fn namespaces() {
+    let xml = concat!(
+        "<fb:FictionBook xmlns:fb=\"http://www.gribuser.ru/xml/fictionbook/2.0\" ",
+        "xmlns:x=\"http://www.w3.org/1999/xlink\" xmlns:o=\"other\">",
+        "<fb:image x:href=\"#a\"/><fb:image x:href=\"#b\"/><o:image/></fb:FictionBook>",
+    );
+    let root = concat!(
+        "<fb:FictionBook xmlns:fb=\"http://www.gribuser.ru/xml/fictionbook/2.0\" ",
+        "xmlns:x=\"http://www.w3.org/1999/xlink\" xmlns:o=\"other\">",
+    );
+
+    assert_eq!(
+        format!("{}<o:image/></fb:FictionBook>", root),
+        clean(xml, &tags(&["--tags", "image"]))
+    );
+    assert_eq!(
+        format!(
+            "{}<fb:image x:href=\"#b\"/><o:image/></fb:FictionBook>",
+            root
+        ),
+        clean(xml, &tags(&["--tags", "fb:image[l:href=#a]"]))
+    );
+    assert_eq!(
+        format!("{}</fb:FictionBook>", root),
+        clean(xml, &tags(&["--tags", "image", "--local-names"]))
+    );
+    assert_eq!(
+        format!(
+            "{}<fb:image x:href=\"#a\"/><fb:image x:href=\"#b\"/></fb:FictionBook>",
+            root
+        ),
+        clean(xml, &tags(&["--tags", "o:image"]))
+    );
+}
+
+const REFS: &str = concat!(
+    "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><body>",
+    "<image l:href=\"#a\"/><image l:href=\"#b\"/><image l:href=\"http://x\"/></body>",
+    "<binary id=\"a\">AA==</binary><binary id=\"c\">AA==</binary></FictionBook>",
+);
+
+#[test]
+fn fix_refs() {
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><body>\
+         <image l:href=\"#a\"/><image l:href=\"http://x\"/></body>\
+         <binary id=\"a\">AA==</binary></FictionBook>",
+        clean(REFS, &tags(&["--tags", "p", "--fix-refs"]))
+    );
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><body>\
+         <image l:href=\"http://x\"/></body></FictionBook>",
+        clean(REFS, &tags(&["--tags", "binary[id=a]", "--fix-refs"]))
+    );
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><body></body></FictionBook>",
+        clean(REFS, &tags(&["--tags", "image", "--fix-refs"]))
+    );
+}
+
+#[test]
+fn scan_refs() {
+    let sorted = |it: &mut dyn Iterator<Item = &[u8]>| {
+        let mut xs: Vec<Vec<u8>> = it.map(|x| x.to_vec()).collect();
+        xs.sort();
+        xs
+    };
+    let tags = tags(&["--tags", "p", "--fix-refs"]);
+    let refs = scan_xml_refs(&mut NsReader::from_str(REFS), &tags).unwrap();
+    assert_eq!(
+        vec![b"a".to_vec(), b"c".to_vec()],
+        sorted(&mut refs.binaries())
+    );
+    assert_eq!(
+        vec![b"a".to_vec(), b"b".to_vec()],
+        sorted(&mut refs.images())
+    );
+    assert_eq!(0, refs.covers().count());
+    assert_eq!(0, refs.large().count());
+}
+
+const COVER: &str = concat!(
+    "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><description><title-info>",
+    "<coverpage><image l:href=\"#cover\"/></coverpage></title-info></description>",
+    "<body><image l:href=\"#a\"/><p>x</p></body>",
+    "<binary id=\"cover\">AA==</binary><binary id=\"a\">AA==</binary></FictionBook>",
+);
+
+#[test]
+fn keep_cover() {
+    let expected = "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><description><title-info>\
+         <coverpage><image l:href=\"#cover\"/></coverpage></title-info></description>\
+         <body><p>x</p></body><binary id=\"cover\">AA==</binary></FictionBook>";
+    assert_eq!(expected, clean(COVER, &tags(&["--keep-cover"])));
+    assert_eq!(
+        expected,
+        clean(COVER, &tags(&["--tags", "p[id]", "--keep-cover"]))
+    );
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><body><p>x</p></body></FictionBook>",
+        clean(COVER, &tags(&["--tags", "description", "--keep-cover"]))
+    );
+}
+
+#[test]
+fn keep_cover_max_binary_size() {
+    let xml = COVER.replace("<binary id=\"cover\">AA==", "<binary id=\"cover\">AAAA");
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><description><title-info>\
+         <coverpage></coverpage></title-info></description><body><p>x</p></body></FictionBook>",
+        clean(&xml, &tags(&["--keep-cover", "--max-binary-size", "2"]))
+    );
+    assert_eq!(
+        clean(&xml, &tags(&["--keep-cover"])).replace("AA==", "AAAA"),
+        clean(&xml, &tags(&["--keep-cover", "--max-binary-size", "3"]))
+    );
+}
+
+const SIZES: &str = concat!(
+    "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><body>",
+    "<image l:href=\"#a\"/><image l:href=\"#b\"/></body>",
+    "<binary id=\"a\">AAAA\nAAA=</binary><binary id=\"b\">AAAA AAAA</binary></FictionBook>",
+);
+
+#[test]
+fn max_binary_size() {
+    assert_eq!(
+        SIZES,
+        clean(SIZES, &tags(&["--tags", "p", "--max-binary-size", "6"]))
+    );
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><body>\
+         <image l:href=\"#a\"/></body><binary id=\"a\">AAAA\nAAA=</binary></FictionBook>",
+        clean(SIZES, &tags(&["--tags", "p", "--max-binary-size", "5"]))
+    );
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\"><body></body></FictionBook>",
+        clean(SIZES, &tags(&["--tags", "p", "--max-binary-size", "4"]))
+    );
+}
+
+#[test]
+fn strip_attrs() {
+    let xml = concat!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\" xml:lang=\"en\">",
+        "<body><p id=\"a\" style=\"b\">x</p><section id=\"s\"><p id=\"c\"/></section>",
+        "<image l:href=\"#i\" id=\"i\"/></body></FictionBook>",
+    );
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\">\
+         <body><p>x</p><section><p/></section><image l:href=\"#i\"/></body></FictionBook>",
+        clean(
+            xml,
+            &tags(&["--tags", "binary", "--strip-attrs", "style,id,xml:lang"])
+        )
+    );
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\" xml:lang=\"en\">\
+         <body><p style=\"b\">x</p><section id=\"s\"><p/></section>\
+         <image id=\"i\"/></body></FictionBook>",
+        clean(
+            xml,
+            &tags(&["--tags", "binary", "--strip-attrs", "p@id,image@l:href"])
+        )
+    );
+    assert_eq!(
+        "<FictionBook xmlns:l=\"http://www.w3.org/1999/xlink\" xml:lang=\"en\">\
+         <body><p id=\"a\" style=\"b\">x</p><section id=\"s\"><p/></section>\
+         <image l:href=\"#i\" id=\"i\"/></body></FictionBook>",
+        clean(
+            xml,
+            &tags(&["--tags", "binary", "--strip-attrs", "section[id=s]/p@id=c"])
+        )
+    );
+}
+
+#[test]
+fn prune_empty() {
+    let xml = concat!(
+        "<FictionBook><body><section>\n <p><image/></p>\n <empty-line/></section>",
+        "<section><p> <image/> </p><p>a<image/></p><!--c--></section></body></FictionBook>",
+    );
+    assert_eq!(
+        "<FictionBook><body><section><p>a</p><!--c--></section></body></FictionBook>",
+        clean(xml, &tags(&["--prune-empty", "p,section,empty-line"]))
+    );
+    assert_eq!(
+        "<FictionBook><body><section>\n \n <empty-line/></section>\
+         <section><p>a</p><!--c--></section></body></FictionBook>",
+        clean(xml, &tags(&["--prune-empty"]))
+    );
+    assert_eq!(
+        "<FictionBook><body><section>\n <p></p>\n <empty-line/></section>\
+         <section><p>  </p><p>a</p><!--c--></section></body></FictionBook>",
+        clean(xml, &tags(&["--prune-empty", "section"]))
+    );
+}
+
+#[test]
+fn prune_stripped() {
+    let xml = concat!(
+        "<FictionBook><body><section><p>a</p><p><!-- c --></p>",
+        "<section><?pi?></section></section></body></FictionBook>",
+    );
+    assert_eq!(
+        "<FictionBook><body><section><p>a</p></section></body></FictionBook>",
+        clean(
+            xml,
+            &tags(&["--prune-empty", "--strip-comments", "--strip-pis"])
+        )
+    );
+    // Kept comments and processing instructions are content.
+    assert_eq!(xml, clean(xml, &tags(&["--prune-empty"])));
+}
+
+#[test]
+fn comments_pis_doctype() {
+    let xml = concat!(
+        "<?xml version=\"1.0\"?><!DOCTYPE FictionBook>",
+        "<?xml-stylesheet href=\"a.css\"?><?pi x?><FictionBook><!--c--><body/></FictionBook>",
+    );
+    assert_eq!(xml, clean(xml, &tags(&["--tags", "p"])));
+    assert_eq!(
+        "<?xml version=\"1.0\"?><FictionBook><body/></FictionBook>",
+        clean(
+            xml,
+            &tags(&[
+                "-t",
+                "p",
+                "--strip-comments",
+                "--strip-pis",
+                "--strip-doctype"
+            ])
+        )
+    );
+    assert_eq!(
+        "<?xml version=\"1.0\"?><!DOCTYPE FictionBook>\
+         <?xml-stylesheet href=\"a.css\"?><FictionBook><!--c--><body/></FictionBook>",
+        clean(xml, &tags(&["-t", "p", "--keep-pis", "^xml-stylesheet"]))
+    );
+}
+
+#[test]
+fn xml_error() {
+    let mut w = Writer::new(Vec::new());
+    let mut r = NsReader::from_str("<FictionBook><p>a</i></FictionBook>");
+    match remove_xml_tags(&mut r, &mut w, &Tags::default()) {
+        Err(e @ Error::Xml { position: 17, .. }) => assert_eq!(None, e.path()),
+        r => panic!("unexpected {:?}", r),
+    }
+}
@@ -0,0 +1,85 @@
+use fb2_clean::*;
+use quick_xml::{NsReader, Writer};
+
+fn repair(xml: &str) -> (String, Repairs) {
+    let mut w = Writer::new(Vec::new());
+    let repairs = repair_xml(&mut NsReader::from_str(xml), &mut w).unwrap();
+    (String::from_utf8(w.into_inner()).unwrap(), repairs)
+}
+
+#[test]
+fn well_formed() {
+    let xml = "<?xml version=\"1.0\"?><a><b x=\"1\">c &amp; d</b><e/></a>";
+    let (s, repairs) = repair(xml);
+    assert_eq!(xml, s);
+    assert!(repairs.is_empty());
+    assert_eq!("", repairs.to_string());
+}
+
+#[test]
+fn close_tags() {
+    let (s, repairs) = repair("<a><b><p>c");
+    assert_eq!("<a><b><p>c</p></b></a>", s);
+    assert_eq!(3, repairs.closed_tags);
+
+    let (s, repairs) = repair("<a><b><i>c</b>d</a>");
+    assert_eq!("<a><b><i>c</i></b>d</a>", s);
+    assert_eq!(1, repairs.closed_tags);
+}
+
+#[test]
+fn escape_amps() {
+    let (s, repairs) = repair("<p>Tom & Jerry &amp; &</p>");
+    assert_eq!("<p>Tom &amp; Jerry &amp; &amp;</p>", s);
+    assert_eq!(2, repairs.escaped_amps);
+}
+
+#[test]
+fn escape_invalid_refs() {
+    let (s, repairs) = repair("<p>&#x; &#; &a b; &#xZ; &#12; &#x1F; &c;</p>");
+    assert_eq!(
+        "<p>&amp;#x; &amp;#; &amp;a b; &amp;#xZ; &#12; &#x1F; &c;</p>",
+        s
+    );
+    assert_eq!(4, repairs.escaped_amps);
+}
+
+#[test]
+fn complete_refs() {
+    let (s, repairs) = repair("<p>a &amp b &lt;&lt c &#33 d & e AT&T &ampx &#x</p>");
+    assert_eq!(
+        "<p>a &amp; b &lt;&lt; c &#33; d &amp; e AT&amp;T &amp;ampx &amp;#x</p>",
+        s
+    );
+    assert_eq!(3, repairs.completed_refs);
+    assert_eq!(4, repairs.escaped_amps);
+}
+
+#[test]
+fn drop_ends() {
+    let (s, repairs) = repair("<a><p>b</i></p></p></a></a>");
+    assert_eq!("<a><p>b</p></a>", s);
+    assert_eq!(3, repairs.dropped_ends);
+    assert_eq!(0, repairs.closed_tags);
+}
+
+#[test]
+fn display() {
+    let (_, repairs) = repair("<a>&</b>");
+    assert_eq!(
+        "closed tags: 1, escaped ampersands: 1, dropped end tags: 1",
+        repairs.to_string()
+    );
+}
+
+#[test]
+fn clean_repaired() {
+    let (s, _) = repair("<FictionBook><body><p>a & b<image/></section></body>");
+    let mut w = Writer::new(Vec::new());
+    let tags = Tags::default();
+    remove_xml_tags(&mut NsReader::from_str(&s), &mut w, &tags).unwrap();
+    assert_eq!(
+        "<FictionBook><body><p>a &amp; b</p></body></FictionBook>",
+        String::from_utf8(w.into_inner()).unwrap()
+    );
+}
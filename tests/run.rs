@@ -2,8 +2,12 @@
 mod common;
 
 use common::*;
-use fb2_clean::Config;
-use std::fs;
+use fb2_clean::{BookStatus, Config, Error, Input, InputFileType};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
 
 fn assert_ne_empty(s: &str) {
     assert_ne!(0, fs::metadata(temp(s)).unwrap().len());
@@ -67,6 +71,43 @@ fn recursive_limit() {
     assert!(!temp("recursive_limit/1/2/3/dummy.fb2").exists());
 }
 
+#[test]
+fn many_inputs() {
+    let i = data("recursive").to_str().unwrap().to_owned();
+    let g = data("recursive/**/*.fb2").to_str().unwrap().to_owned();
+    let f = data("dummy.fb2").to_str().unwrap().to_owned();
+    let o = temp("many_inputs").to_str().unwrap().to_owned();
+    run(&["-e", "-i", &i, "-i", &g, "-i", &f, "-o", &o, "-r", "1"]);
+
+    for f in [
+        "recursive/dummy.fb2",
+        "recursive/1/dummy.fb2",
+        "1/2/3/dummy.fb2",
+        "1/dummy.fb2",
+        "dummy.fb2",
+    ] {
+        let f = temp(&format!("many_inputs/{}", f));
+        assert!(f.exists(), "{}", f.display());
+    }
+    assert!(!temp("many_inputs/recursive/1/2/3/dummy.fb2").exists());
+}
+
+#[test]
+fn overlapping_glob() {
+    let g = data("recursive/**").to_str().unwrap().to_owned();
+    let o = temp("overlapping_glob").to_str().unwrap().to_owned();
+    let mut c = cfg(&["-e", "-i", &g, "-o", &o]);
+    let _ = fs::remove_dir_all(&c.output.dir);
+    c.output.create_dirs().unwrap();
+
+    let r = c.run_report().unwrap();
+    assert_eq!(3, r.books.len());
+    assert_eq!(3, r.count(BookStatus::Cleaned));
+    for f in ["dummy.fb2", "1/dummy.fb2", "1/2/3/dummy.fb2"] {
+        assert!(temp(&format!("overlapping_glob/{}", f)).exists(), "{}", f);
+    }
+}
+
 #[test]
 fn zip() {
     let i = unzip_to("zip");
@@ -109,3 +150,465 @@ fn exit_on_err() {
     let c = cfg(&["-e", "-i", &i, "-o", &o]);
     c.run().unwrap_err();
 }
+
+#[test]
+fn errors() {
+    let dir = temp("errors");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("cleaned")).unwrap();
+    let o = dir.join("cleaned").to_str().unwrap().to_owned();
+
+    let i = dir.join("book.fb2");
+    fs::write(&i, "<FictionBook><p>a</i></FictionBook>").unwrap();
+    let c = cfg(&["-e", "-i", i.to_str().unwrap(), "-o", &o]);
+    match c.run() {
+        Err(e @ Error::Xml { position: 17, .. }) => assert_eq!(Some(&*i), e.path()),
+        r => panic!("unexpected {:?}", r),
+    }
+
+    let i = dir.join("book.fb2.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&i).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("book.txt", options).unwrap();
+    zip.finish().unwrap();
+    let c = cfg(&["-e", "-i", i.to_str().unwrap(), "-o", &o]);
+    match c.run() {
+        Err(Error::NoFb2InArchive(p)) => assert_eq!(i, p.to_path_buf()),
+        r => panic!("unexpected {:?}", r),
+    }
+
+    let i = data("recursive/1/2").to_str().unwrap().to_owned();
+    let c = cfg(&["-i", &i, "-o", &o]);
+    match c.run() {
+        Err(Error::NoBookInDirectory(_)) => (),
+        r => panic!("unexpected {:?}", r),
+    }
+}
+
+#[test]
+fn report() {
+    let dir = temp("report");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy(data("book.fb2.zip"), dir.join("book.fb2.zip")).unwrap();
+    fs::write(dir.join("broken.fb2"), "<FictionBook><p></i></FictionBook>").unwrap();
+
+    let i = dir.to_str().unwrap().to_owned();
+    let mut c = cfg(&["-i", &i]);
+    c.output.create_dirs().unwrap();
+
+    let r = c.run_report().unwrap();
+    assert_eq!(2, r.books.len());
+    let (book, broken) = (&r.books[0], &r.books[1]);
+
+    assert_eq!(BookStatus::Cleaned, book.status);
+    assert_eq!(&*dir.join("book.fb2.zip"), &*book.src);
+    assert_eq!(&*temp("report/cleaned/book.fb2.zip"), &*book.dest);
+    assert_eq!(fs::metadata(&book.src).unwrap().len(), book.input_size);
+    assert_eq!(
+        fs::metadata(&book.dest).ok().map(|m| m.len()),
+        book.output_size
+    );
+    assert_eq!(Some(&1), book.removed.tags.get("coverpage"));
+    assert!(book.removed.tags.get("binary").is_some_and(|&n| n > 0));
+    assert!(book.removed_bytes > 0);
+    assert!(book.error.is_none());
+
+    assert_eq!(BookStatus::Failed, broken.status);
+    assert!(matches!(broken.error, Some(Error::Xml { .. })));
+    assert_eq!(None, broken.output_size);
+
+    let r = c.run_report().unwrap();
+    assert_eq!(BookStatus::Skipped, r.books[0].status);
+    assert_eq!(0, r.count(BookStatus::Cleaned));
+
+    fs::remove_file(dir.join("broken.fb2")).unwrap();
+    let r = cfg(&["-i", &i, "--force"]).run_report().unwrap();
+    assert_eq!(BookStatus::Overwritten, r.books[0].status);
+    assert_eq!(&*dir.join("book.fb2.zip"), &*r.books[0].dest);
+}
+
+#[test]
+fn fix_refs() {
+    let i = data("book.fb2.zip").to_str().unwrap().to_owned();
+    let o = temp("fix_refs").to_str().unwrap().to_owned();
+    run(&[
+        "-e",
+        "-i",
+        &i,
+        "-o",
+        &o,
+        "--unzip",
+        "--tags",
+        "coverpage",
+        "--fix-refs",
+    ]);
+
+    let book = fs::read_to_string(temp("fix_refs/book.fb2")).unwrap();
+    assert!(!book.contains("<image"));
+    assert!(!book.contains("<binary"));
+}
+
+#[test]
+fn keep_cover() {
+    let i = data("book.fb2.zip").to_str().unwrap().to_owned();
+    let o = temp("keep_cover").to_str().unwrap().to_owned();
+    run(&["-e", "-i", &i, "-o", &o, "--unzip", "--keep-cover"]);
+
+    let book = fs::read_to_string(temp("keep_cover/book.fb2")).unwrap();
+    assert!(book.contains("<image l:href=\"#cover.jpg\"/></coverpage>"));
+    assert_eq!(1, book.matches("<binary").count());
+}
+
+#[test]
+fn utf8() {
+    let i = temp("utf8/book.fb2");
+    let _ = fs::create_dir_all(i.parent().unwrap());
+    let xml =
+        "<?xml version=\"1.0\" encoding=\"windows-1251\"?><FictionBook><p>Я</p></FictionBook>";
+    fs::write(&i, encoding_rs::WINDOWS_1251.encode(xml).0).unwrap();
+
+    let i = i.to_str().unwrap().to_owned();
+    run(&["-e", "-i", &i, "--utf8"]);
+
+    let book = fs::read_to_string(temp("utf8/cleaned/book.fb2")).unwrap();
+    assert_eq!(xml.replace("windows-1251", "utf-8"), book);
+}
+
+#[test]
+fn lenient() {
+    let i = temp("lenient/book.fb2");
+    let _ = fs::create_dir_all(i.parent().unwrap());
+    fs::write(&i, "<FictionBook><body><p>a & b</i></body>").unwrap();
+    let i = i.to_str().unwrap().to_owned();
+
+    let _ = fs::remove_dir_all(temp("lenient/cleaned"));
+    cfg(&["-e", "-i", &i]).run().unwrap_err();
+
+    run(&["-e", "-i", &i, "--lenient"]);
+    let book = fs::read_to_string(temp("lenient/cleaned/book.fb2")).unwrap();
+    assert_eq!(
+        "<FictionBook><body><p>a &amp; b</p></body></FictionBook>",
+        book
+    );
+}
+
+#[test]
+fn validate() {
+    let i = data("book.fb2.zip").to_str().unwrap().to_owned();
+    let o = temp("validate").to_str().unwrap().to_owned();
+    run(&["-e", "-i", &i, "-o", &o, "--validate", "fail"]);
+    assert_ne_empty("validate/book.fb2.zip");
+
+    let o = temp("validate_fail").to_str().unwrap().to_owned();
+    let mut c = cfg(&[
+        "-e",
+        "-i",
+        &i,
+        "-o",
+        &o,
+        "-t",
+        "genre",
+        "--validate",
+        "fail",
+    ]);
+    let _ = fs::remove_dir_all(&c.output.dir);
+    c.output.create_dirs().unwrap();
+    c.run().unwrap_err();
+    assert!(!temp("validate_fail/book.fb2.zip").exists());
+
+    run(&["-e", "-i", &i, "-o", &o, "-t", "genre", "--validate"]);
+    assert_ne_empty("validate_fail/book.fb2.zip");
+}
+
+#[test]
+fn style() {
+    let i = data("book.fb2.zip").to_str().unwrap().to_owned();
+    for style in ["minify", "pretty"] {
+        let o = temp(&format!("style/{}", style))
+            .to_str()
+            .unwrap()
+            .to_owned();
+        run(&["-e", "-i", &i, "-o", &o, "--unzip", "--style", style]);
+
+        let book = fs::read_to_string(temp(&format!("style/{}/book.fb2", style))).unwrap();
+        assert_eq!(style == "pretty", book.contains("\n  <description>"));
+        assert_eq!(style == "pretty", book.ends_with("</FictionBook>\n"));
+        assert!(!book.contains("<coverpage"));
+    }
+}
+
+#[cfg(feature = "images")]
+#[test]
+fn recompress_images() {
+    let i = data("book.fb2.zip").to_str().unwrap().to_owned();
+    let o = temp("recompress_images").to_str().unwrap().to_owned();
+    run(&[
+        "-e",
+        "-i",
+        &i,
+        "-o",
+        &o,
+        "--unzip",
+        "-t",
+        "genre",
+        "--max-width",
+        "64",
+    ]);
+
+    let book = fs::read_to_string(temp("recompress_images/book.fb2")).unwrap();
+    assert!(!book.contains("<genre"));
+    let binary = book.split("<binary").nth(1).unwrap();
+    assert!(binary.contains("content-type=\"image/jpeg\""));
+    assert!(binary.len() < 20_000);
+}
+
+#[cfg(feature = "images")]
+#[test]
+fn recompress_images_default_tags() {
+    let i = data("book.fb2.zip").to_str().unwrap().to_owned();
+    let o = temp("recompress_images_default_tags")
+        .to_str()
+        .unwrap()
+        .to_owned();
+    run(&["-e", "-i", &i, "-o", &o, "--unzip", "--max-width", "64"]);
+
+    let book = fs::read_to_string(temp("recompress_images_default_tags/book.fb2")).unwrap();
+    assert!(book.contains("<coverpage>"));
+    let binary = book.split("<binary").nth(1).unwrap();
+    assert!(binary.contains("content-type=\"image/jpeg\""));
+}
+
+#[test]
+fn dry_run() {
+    let dir = temp("dry_run");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::copy(data("book.fb2.zip"), dir.join("book.fb2.zip")).unwrap();
+    fs::copy(data("book.fb2.zip"), dir.join("sub/book.fb2.zip")).unwrap();
+
+    let i = dir.to_str().unwrap().to_owned();
+    let o = dir.join("out");
+    let o = o.to_str().unwrap();
+    for args in [
+        vec!["-i", &i, "-o", o, "-r", "1", "--dry-run"],
+        vec!["-i", &i, "-r", "1", "--dry-run", "--force", "--unzip"],
+    ] {
+        let r = cfg(&args).run_report().unwrap();
+        assert_eq!(2, r.count(BookStatus::DryRun));
+        let book = &r.books[0];
+        assert_eq!(None, book.output_size);
+        assert_eq!(Some(&1), book.removed.tags.get("coverpage"));
+        assert!(book.removed_bytes > 0);
+        assert_eq!(2 * book.removed_bytes, r.removed_bytes());
+        assert_eq!(Some(&2), r.removed().tags.get("coverpage"));
+    }
+
+    assert!(!dir.join("out").exists());
+    assert!(!dir.join("cleaned").exists());
+    assert!(dir.join("book.fb2.zip").exists());
+    assert!(!dir.join("book.fb2").exists());
+}
+
+fn book_fb2() -> Vec<u8> {
+    let mut zip = zip::ZipArchive::new(fs::File::open(data("book.fb2.zip")).unwrap()).unwrap();
+    let mut xml = Vec::new();
+    zip.by_index(0).unwrap().read_to_end(&mut xml).unwrap();
+    xml
+}
+
+fn zip_entries(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut zip = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    (0..zip.len())
+        .map(|i| {
+            let mut f = zip.by_index(i).unwrap();
+            let mut bytes = Vec::new();
+            f.read_to_end(&mut bytes).unwrap();
+            (f.name().to_owned(), bytes)
+        })
+        .collect()
+}
+
+#[test]
+fn multi_book_zip() {
+    let dir = temp("multi_book_zip");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let i = dir.join("fb2-1-3.zip");
+    let broken = b"<FictionBook><p></i></FictionBook>";
+    let mut zip = zip::ZipWriter::new(fs::File::create(&i).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("1.fb2", options).unwrap();
+    zip.write_all(&book_fb2()).unwrap();
+    zip.start_file("readme.txt", options).unwrap();
+    zip.write_all(b"readme").unwrap();
+    zip.add_directory("sub/", options).unwrap();
+    zip.start_file("sub/2.FB2", options).unwrap();
+    zip.write_all(&book_fb2()).unwrap();
+    zip.start_file("sub/3.fb2", options).unwrap();
+    zip.write_all(broken).unwrap();
+    zip.finish().unwrap();
+
+    assert_eq!(
+        InputFileType::Zip,
+        match Input::new(&i).unwrap() {
+            Input::File(f) => f.ty,
+            _ => panic!("not a file"),
+        }
+    );
+
+    let i = i.to_str().unwrap().to_owned();
+    let r = cfg(&["-i", &i, "--dry-run"]).run_report().unwrap();
+    assert_eq!(BookStatus::DryRun, r.books[0].status);
+    assert_eq!(Some(&2), r.books[0].removed.tags.get("coverpage"));
+    assert_eq!(1, r.books[0].failed_entries);
+    assert!(!dir.join("cleaned").exists());
+    let entries: Vec<_> = r.books[0]
+        .entries
+        .iter()
+        .map(|e| (e.name.as_str(), e.status))
+        .collect();
+    assert_eq!(
+        vec![
+            ("1.fb2", BookStatus::DryRun),
+            ("sub/2.FB2", BookStatus::DryRun),
+            ("sub/3.fb2", BookStatus::Failed)
+        ],
+        entries
+    );
+    assert_eq!(
+        Some(&1),
+        r.books[0].entries[0].removed.tags.get("coverpage")
+    );
+
+    let mut c = cfg(&["-i", &i, "--unzip"]);
+    c.output.create_dirs().unwrap();
+    let r = c.run_report().unwrap();
+    assert_eq!(BookStatus::Partial, r.books[0].status);
+    assert_eq!(1, r.books[0].failed_entries);
+    assert!(r.books[0].error.is_none());
+    assert_eq!(BookStatus::Cleaned, r.books[0].entries[1].status);
+    assert!(matches!(
+        r.books[0].entries[2].error,
+        Some(Error::Xml { .. })
+    ));
+    assert_eq!(&*dir.join("cleaned/fb2-1-3.zip"), &*r.books[0].dest);
+    assert_eq!(Some(&2), r.books[0].removed.tags.get("coverpage"));
+
+    let entries = zip_entries(&r.books[0].dest);
+    let names: Vec<_> = entries.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(
+        vec!["1.fb2", "readme.txt", "sub/", "sub/2.FB2", "sub/3.fb2"],
+        names
+    );
+    let cleaned = &entries[0].1;
+    assert!(cleaned.len() < book_fb2().len());
+    assert!(!String::from_utf8_lossy(cleaned).contains("<binary"));
+    assert_eq!(cleaned, &entries[3].1);
+    assert_eq!(b"readme", &*entries[1].1);
+    assert_eq!(broken, &*entries[4].1);
+
+    let mut c = cfg(&["-e", "-i", &i, "-o", dir.join("e").to_str().unwrap()]);
+    c.output.create_dirs().unwrap();
+    let r = c.run_report().unwrap();
+    match r.error {
+        Some(e @ Error::Xml { .. }) => {
+            assert_eq!(Some(&*Path::new(&i).join("sub/3.fb2")), e.path())
+        }
+        e => panic!("unexpected {:?}", e),
+    }
+    assert_eq!(BookStatus::Failed, r.books[0].status);
+    assert_eq!(3, r.books[0].entries.len());
+    assert_eq!("sub/3.fb2", r.books[0].entries[2].name);
+    assert_eq!(BookStatus::Failed, r.books[0].entries[2].status);
+}
+
+#[test]
+fn non_book_zip() {
+    let dir = temp("non_book_zip");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy(data("book.fb2.zip"), dir.join("book.fb2.zip")).unwrap();
+    let mut zip = zip::ZipWriter::new(fs::File::create(dir.join("photos.zip")).unwrap());
+    zip.start_file("a.jpg", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    zip.write_all(b"\xff\xd8\xff\xe0").unwrap();
+    zip.finish().unwrap();
+
+    let i = dir.to_str().unwrap().to_owned();
+    let mut c = cfg(&["-e", "-i", &i]);
+    c.output.create_dirs().unwrap();
+    let r = c.run_report().unwrap();
+    assert!(r.error.is_none());
+    assert_eq!(1, r.books.len());
+    assert_eq!(BookStatus::Cleaned, r.books[0].status);
+}
+
+#[test]
+fn containers() {
+    let i = data("book.fb2.zip").to_str().unwrap().to_owned();
+    let o = temp("containers/fb2").to_str().unwrap().to_owned();
+    run(&["-i", &i, "-o", &o, "--unzip"]);
+    let exp = fs::read(temp("containers/fb2/book.fb2")).unwrap();
+
+    for ext in ["fb2.zip", "fbz", "fb2.gz", "fb2.bz2", "fb2.xz"] {
+        let o = temp(&format!("containers/{}", ext));
+        let o = o.to_str().unwrap();
+        run(&["-i", &i, "-o", o, "--container", ext]);
+
+        let packed = temp(&format!("containers/{}/book.{}", ext, ext));
+        assert!(fs::metadata(&packed).unwrap().len() < exp.len() as u64);
+
+        let back = temp(&format!("containers/{}/back", ext));
+        let back = back.to_str().unwrap();
+        run(&["-i", packed.to_str().unwrap(), "-o", back, "--unzip"]);
+        let got = fs::read(temp(&format!("containers/{}/back/book.fb2", ext))).unwrap();
+        assert_eq!(exp, got, "{}", ext);
+    }
+}
+
+#[test]
+fn sniff() {
+    let dir = temp("sniff");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("book.xml"), book_fb2()).unwrap();
+    fs::copy(data("book.fb2.zip"), dir.join("book2")).unwrap();
+    fs::write(dir.join("notes.txt"), "notes").unwrap();
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(&book_fb2()).unwrap();
+    fs::write(dir.join("Tolstoy.War.gz"), gz.finish().unwrap()).unwrap();
+
+    let i = dir.to_str().unwrap().to_owned();
+    let mut c = cfg(&["-i", &i, "--sniff"]);
+    c.output.create_dirs().unwrap();
+    let r = c.run_report().unwrap();
+    assert_eq!(3, r.count(BookStatus::Cleaned));
+    assert_eq!(3, r.books.len());
+    assert_ne!(
+        0,
+        fs::metadata(dir.join("cleaned/book.xml.fb2"))
+            .unwrap()
+            .len()
+    );
+    assert_ne!(
+        0,
+        fs::metadata(dir.join("cleaned/Tolstoy.War.gz.fb2.gz"))
+            .unwrap()
+            .len()
+    );
+    assert_ne!(
+        0,
+        fs::metadata(dir.join("cleaned/book2.fb2.zip"))
+            .unwrap()
+            .len()
+    );
+}
+
+#[test]
+fn stdout_needs_one_book() {
+    let mut c = cfg(&["-i", data("").to_str().unwrap()]);
+    c.stdout = true;
+    assert!(matches!(c.run_report(), Err(Error::InvalidValue(_))));
+}
@@ -0,0 +1,134 @@
+#[allow(unused)]
+mod common;
+
+use common::*;
+use std::{
+    fs,
+    io::{Cursor, Read, Write},
+    process::{Command, Output, Stdio},
+};
+use zip::ZipArchive;
+
+fn run_bin(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_fb2-clean"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn cleaned_book(dir: &str) -> Vec<u8> {
+    let i = data("book.fb2.zip");
+    let o = temp(dir);
+    let _ = fs::remove_dir_all(&o);
+    let out = run_bin(
+        &["-i", i.to_str().unwrap(), "-o", o.to_str().unwrap(), "-Z"],
+        b"",
+    );
+    assert!(out.status.success());
+    let book = fs::read(o.join("book.fb2")).unwrap();
+    let _ = fs::remove_dir_all(&o);
+    book
+}
+
+#[test]
+fn stdin_to_stdout() {
+    let exp = cleaned_book("stdin_to_stdout");
+    let zipped = fs::read(data("book.fb2.zip")).unwrap();
+
+    let out = run_bin(&["-i", "-", "-o", "-", "-Z"], &zipped);
+    assert!(out.status.success());
+    assert_eq!(exp, out.stdout);
+
+    let out = run_bin(&["-i", "-", "-o", "-"], &exp);
+    assert!(out.status.success());
+    assert_eq!(exp, out.stdout);
+}
+
+#[test]
+fn zipped_stdout() {
+    let exp = cleaned_book("zipped_stdout");
+    let zipped = fs::read(data("book.fb2.zip")).unwrap();
+
+    let out = run_bin(&["-i", "-", "-o", "-"], &zipped);
+    assert!(out.status.success());
+
+    let mut zip = ZipArchive::new(Cursor::new(out.stdout)).unwrap();
+    assert_eq!(1, zip.len());
+    let mut entry = zip.by_index(0).unwrap();
+    assert_eq!("book.fb2", entry.name());
+    let mut got = Vec::new();
+    entry.read_to_end(&mut got).unwrap();
+    assert_eq!(exp, got);
+}
+
+#[test]
+fn not_a_book() {
+    let out = run_bin(&["-i", "-", "-o", "-"], b"notes");
+    assert!(!out.status.success());
+    assert!(out.stdout.is_empty());
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(stderr.contains("Is not a FictionBook '-'"), "{}", stderr);
+    assert!(!stderr.contains("NotABook"), "{}", stderr);
+    assert_eq!(
+        1,
+        stderr.matches("Is not a FictionBook").count(),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn sniffed_head() {
+    let decl = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n";
+    let root = "<FictionBook xmlns=\"http://www.gribuser.ru/xml/fictionbook/2.0\"><body><p>";
+    let end = "</p></body></FictionBook>";
+    // The head of 8 KiB ends in the middle of `я`.
+    let pad = "a".repeat(8191 - decl.len() - root.len());
+    let cut = format!("{}{}{}{}{}", decl, root, pad, "я".repeat(10), end);
+    // The root follows a comment longer than the head.
+    let late = format!("{}<!-- {} -->{}x{}", decl, "c".repeat(10000), root, end);
+
+    for book in [cut, late] {
+        let out = run_bin(&["-i", "-", "-o", "-"], book.as_bytes());
+        let stderr = String::from_utf8(out.stderr).unwrap();
+        assert!(out.status.success(), "{}", stderr);
+        assert!(!stderr.contains("Mis-declared"), "{}", stderr);
+    }
+}
+
+#[test]
+fn misdeclared_once() {
+    let book =
+        "<?xml version=\"1.0\" encoding=\"windows-1251\"?><FictionBook><p>Я</p></FictionBook>";
+    let out = run_bin(
+        &["-i", "-", "-o", "-", "--utf8", "--fix-refs"],
+        book.as_bytes(),
+    );
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(out.status.success(), "{}", stderr);
+    assert_eq!(
+        1,
+        stderr
+            .matches("Mis-declared encoding '-': 'windows-1251', decoding as 'UTF-8'")
+            .count(),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn dry_run_nothing_removed() {
+    let book = "<FictionBook><body><p>x</p></body></FictionBook>";
+    let out = run_bin(&["-i", "-", "--dry-run"], book.as_bytes());
+    assert!(out.status.success());
+    let log = [out.stdout, out.stderr].concat();
+    let log = String::from_utf8(log).unwrap();
+    assert!(log.contains("Would remove from '-': 0 bytes\n"), "{}", log);
+    assert!(log.contains("Would remove in total: 0 bytes\n"), "{}", log);
+    assert!(!log.contains("; \n"), "{}", log);
+}
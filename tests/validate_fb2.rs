@@ -0,0 +1,89 @@
+use fb2_clean::*;
+use quick_xml::NsReader;
+
+const DESCRIPTION: &str = concat!(
+    "<description><title-info><genre>prose</genre><author><nickname>a</nickname></author>",
+    "<book-title>b</book-title><lang>en</lang></title-info>",
+    "<document-info><author><nickname>a</nickname></author><date>2000</date><id>1</id>",
+    "<version>1.0</version></document-info></description>",
+);
+
+fn book(description: &str, body: &str) -> String {
+    format!(
+        "<FictionBook xmlns=\"http://www.gribuser.ru/xml/fictionbook/2.0\" \
+         xmlns:l=\"http://www.w3.org/1999/xlink\">{}<body>{}</body></FictionBook>",
+        description, body
+    )
+}
+
+fn validate(xml: &str) -> Vec<String> {
+    validate_fb2(&mut NsReader::from_str(xml))
+        .unwrap()
+        .into_iter()
+        .map(|v| v.to_string())
+        .collect()
+}
+
+#[test]
+fn valid() {
+    let body = concat!(
+        "<title><p>t</p></title><section><title><p>1</p></title><image l:href=\"#i\"/>",
+        "<p>a <emphasis>b</emphasis> &amp; c</p><empty-line/><poem><stanza><v>v</v></stanza>",
+        "</poem><table><tr><td>1</td></tr></table></section>",
+    );
+    assert!(validate(&book(DESCRIPTION, body)).is_empty());
+}
+
+#[test]
+fn missing() {
+    let d = DESCRIPTION.replace("<genre>prose</genre>", "");
+    assert_eq!(
+        vec!["/FictionBook/description/title-info: missing tag 'genre'"],
+        validate(&book(&d, "<section/>"))
+    );
+    assert_eq!(
+        vec!["/FictionBook/body: missing tag 'section'"],
+        validate(&book(DESCRIPTION, ""))
+    );
+    assert_eq!(
+        vec!["/FictionBook/body/section/table: missing tag 'tr'"],
+        validate(&book(DESCRIPTION, "<section><table/></section>"))
+    );
+}
+
+#[test]
+fn misplaced() {
+    let d = DESCRIPTION.replace(
+        "<book-title>b</book-title><lang>en</lang>",
+        "<lang>en</lang><book-title>b</book-title>",
+    );
+    assert_eq!(
+        vec!["/FictionBook/description/title-info: misplaced tag 'book-title'"],
+        validate(&book(&d, "<section/>"))
+    );
+}
+
+#[test]
+fn too_many() {
+    let d = DESCRIPTION.replace("<lang>en</lang>", "<lang>en</lang><lang>ru</lang>");
+    assert_eq!(
+        vec!["/FictionBook/description/title-info: too many tags 'lang'"],
+        validate(&book(&d, "<section/>"))
+    );
+}
+
+#[test]
+fn unexpected() {
+    assert_eq!(
+        vec![
+            "/FictionBook/body/section: unexpected tag 'v'",
+            "/FictionBook/body/section/p: unexpected tag 'p'",
+            "/FictionBook/body/section: unexpected text",
+        ],
+        validate(&book(
+            DESCRIPTION,
+            "<section><v>a</v><p><p>b</p></p>c<x:y xmlns:x=\"x\"><z/></x:y>d</section>"
+        ))
+    );
+    assert_eq!(vec!["/: unexpected tag 'html'"], validate("<html/>"));
+}
@@ -0,0 +1,74 @@
+use encoding_rs::{KOI8_R, UTF_8, UTF_16LE, WINDOWS_1251};
+use fb2_clean::*;
+use std::borrow::Cow;
+
+const BOOK: &str = "<FictionBook><body><p>Привет</p></body></FictionBook>";
+
+fn decl(encoding: &str) -> String {
+    format!("<?xml version=\"1.0\" encoding=\"{}\"?>", encoding)
+}
+
+fn utf8() -> String {
+    decl("utf-8") + BOOK
+}
+
+#[test]
+fn detect() {
+    let xml = decl("windows-1251") + BOOK;
+    let cp1251 = WINDOWS_1251.encode(&xml).0;
+    assert_eq!(WINDOWS_1251.name(), detect_xml_encoding(&cp1251));
+    assert_eq!(UTF_8.name(), detect_xml_encoding(utf8().as_bytes()));
+    assert_eq!("UTF-8", detect_xml_encoding(BOOK.as_bytes()));
+    assert_eq!(UTF_16LE.name(), detect_xml_encoding(b"\xFF\xFE<\0"));
+}
+
+#[test]
+fn legacy_to_utf8() {
+    for (encoding, label) in [(WINDOWS_1251, "windows-1251"), (KOI8_R, "KOI8-R")] {
+        let xml = decl(label) + BOOK;
+        let xml = encoding.encode(&xml).0;
+        assert_eq!(utf8().as_bytes(), &*xml_to_utf8(&xml));
+    }
+
+    let xml = WINDOWS_1251
+        .encode("<?xml version='1.0' encoding='cp1251'?><p>Я</p>")
+        .0;
+    assert_eq!(
+        "<?xml version='1.0' encoding=\"utf-8\"?><p>Я</p>".as_bytes(),
+        &*xml_to_utf8(&xml)
+    );
+}
+
+#[test]
+fn keep_utf8() {
+    let xml = utf8();
+    assert!(matches!(xml_to_utf8(xml.as_bytes()), Cow::Borrowed(_)));
+    assert!(matches!(xml_to_utf8(BOOK.as_bytes()), Cow::Borrowed(_)));
+}
+
+#[test]
+fn bom() {
+    let mut xml = vec![0xFF, 0xFE];
+    for u in (decl("utf-16") + BOOK).encode_utf16() {
+        xml.extend_from_slice(&u.to_le_bytes());
+    }
+    assert_eq!(utf8().as_bytes(), &*xml_to_utf8(&xml));
+
+    let xml = [&b"\xEF\xBB\xBF"[..], utf8().as_bytes()].concat();
+    assert_eq!(utf8().as_bytes(), &*xml_to_utf8(&xml));
+}
+
+#[test]
+fn misdeclared() {
+    let xml = decl("windows-1251") + BOOK;
+    assert_eq!(utf8().as_bytes(), &*xml_to_utf8(xml.as_bytes()));
+
+    for label in ["utf-8", "unknown", "utf-16"] {
+        let xml = decl(label) + BOOK;
+        let xml = WINDOWS_1251.encode(&xml).0;
+        assert_eq!(utf8().as_bytes(), &*xml_to_utf8(&xml));
+    }
+
+    let xml = WINDOWS_1251.encode(BOOK).0;
+    assert_eq!(BOOK.as_bytes(), &*xml_to_utf8(&xml));
+}